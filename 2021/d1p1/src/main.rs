@@ -41,22 +41,31 @@ To do this, count the number of times a depth measurement increases from the pre
 260 (decreased)
 263 (increased)
 */
-#![feature(stdin_forwarders)]
-use std::io;
+use aocutil::{
+    parse::{self, ParseError},
+    window::Sliding,
+};
+use std::{io, process};
 
 fn main() {
     println!("Paste sonar report below:");
-    let mut inbuf = String::from("");
-    io::stdin().read_line(&mut inbuf);
-    let mut last_pos: i32 = inbuf.trim().parse().unwrap();
-    println!("Staritng pos is {}", last_pos);
-    let mut increases = 0;
-    for line in io::stdin().lines() {
-        let new_pos: i32 = line.unwrap().trim().parse().unwrap();
-        if new_pos > last_pos {
-            increases += 1;
+    let input = io::read_to_string(io::stdin()).unwrap();
+    match increases(&input) {
+        Ok(increases) => println!("{} increases", increases),
+        Err(e) => {
+            eprintln!("Bad sonar report: {}", e);
+            process::exit(1);
         }
-        last_pos = new_pos;
     }
-    println!("{} increases", increases);
+}
+
+fn increases(report: &str) -> Result<usize, ParseError> {
+    let depths: Vec<i32> = parse::lines(report.trim_end(), |c| c.int())?;
+    Ok(depths.iter().count_increases(1))
+}
+
+#[test]
+fn test_example() {
+    let report = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+    assert_eq!(Ok(7), increases(report));
 }
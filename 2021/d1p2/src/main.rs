@@ -38,33 +38,32 @@ In this example, there are 5 sums that are larger than the previous sum.
 Consider sums of a three-measurement sliding window. How many sums are larger than the previous sum?
 
 */
-#![feature(stdin_forwarders)]
-use std::io;
-use std::vec::Vec;
+use aocutil::{
+    parse::{self, ParseError},
+    window::Sliding,
+};
+use std::{io, process};
 
 fn main() {
     println!("Paste sonar report below:");
-    let mut inbuf = String::from("");
-    io::stdin().read_line(&mut inbuf);
-    let mut last_pos: i32 = inbuf.trim().parse().unwrap();
-    println!("Staritng pos is {}", last_pos);
-
-    let mut window: Vec<i32> = vec!(last_pos);
-    let mut last_sum = 0;
-    let mut increases = 0;
-    for line in io::stdin().lines() {
-        let new_pos: i32 = line.unwrap().trim().parse().unwrap();
-        window.push(new_pos);
-        if window.len() == 3 {
-            // Calc
-            let new_sum = window.iter().sum();
-            if last_sum > 0 && new_sum > last_sum {
-                increases += 1;
-            }
-            last_sum = new_sum;
-            window.remove(0);
+    let input = io::read_to_string(io::stdin()).unwrap();
+    match increases(&input) {
+        Ok(increases) => println!("{} increases", increases),
+        Err(e) => {
+            eprintln!("Bad sonar report: {}", e);
+            process::exit(1);
         }
     }
-    println!("{} increases", increases);
 }
- 
\ No newline at end of file
+
+/* Sums of three-measurement windows, compared one window to the next. */
+fn increases(report: &str) -> Result<usize, ParseError> {
+    let depths: Vec<i32> = parse::lines(report.trim_end(), |c| c.int())?;
+    Ok(depths.iter().count_increases(3))
+}
+
+#[test]
+fn test_example() {
+    let report = "199\n200\n208\n210\n200\n207\n240\n269\n260\n263\n";
+    assert_eq!(Ok(5), increases(report));
+}
@@ -35,27 +35,34 @@ Calculate the horizontal position and depth you would have after following the p
 
 
 */
-#![feature(stdin_forwarders)]
-use std::io;
+use aocutil::submarine::{self, Plain, Submarine};
+use std::{env, io, process};
 
 fn main() {
-    let mut depth = 0;
-    let mut pos = 0;
-    let mut lc = 0;
-    println!("Enter submarine commands:");
-    for line in io::stdin().lines() {
-        lc += 1;
-        let line = line.unwrap();
-        let args: Vec<&str> = line.split(" ").collect();
-        let command = args[0];
-        let amt: i32 = args[1].parse().unwrap();
-        match command {
-            "up" => depth -= amt,
-            "down" => depth += amt,
-            "forward" => pos += amt,
-            _ => panic!("Invalid command ({}) at line #{}", command, lc)
+    /* --csv prints the trajectory alone, ready for plotting. */
+    let csv = env::args().any(|arg| arg == "--csv");
+    if !csv {
+        println!("Enter submarine commands:");
+    }
+    let input = io::read_to_string(io::stdin()).unwrap();
+    let course = match submarine::parse_course(&input) {
+        Ok(course) => course,
+        Err(e) => {
+            eprintln!("Bad course: {}", e);
+            process::exit(1);
+        }
+    };
+    let trajectory = Submarine::new(Plain).replay(&course);
+    if csv {
+        submarine::write_trajectory(&mut io::stdout(), &trajectory).unwrap();
+    } else {
+        for state in &trajectory[1..] {
+            println!(
+                "We are now at a depth of {}, position {}, mult = {}",
+                state.depth,
+                state.position,
+                state.product()
+            );
         }
-        println!("We are now at a depth of {}, position {}, mult = {}", depth, pos, depth*pos);
     }
 }
- 
\ No newline at end of file
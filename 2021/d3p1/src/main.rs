@@ -36,46 +36,32 @@ Use the binary numbers in your diagnostic report to calculate the gamma rate and
 
 
 */
-#![feature(stdin_forwarders)]
-use std::io;
+use aocutil::bits::BitColumns;
+use std::{io, process};
 
 fn main() {
     println!("Enter submarine diagnostic report:");
-    let dlines: Vec<String> = io::stdin().lines().map(|s| s.unwrap()).collect();
-    let max_bits = dlines.iter().map(|s| {s.len()}).max().unwrap();
-    let digits = dlines.iter().map(|s| {i16::from_str_radix(s.trim(), 2).unwrap()});
-    let mut gcount = [0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0];
-    let mut ninputs = 0;
-    for digit in digits {
-        for pos in 0..max_bits {
-            let mask = 1 << pos;
-            println!("digit = {:b} mask = {:b}, digit & mask = {:b}", digit, mask, digit & mask);
-            if digit & mask == mask {
-                println!("1 found in pos = {}", pos);
-                gcount[pos] += 1;
-            }
+    let input = io::read_to_string(io::stdin()).unwrap();
+    let report = match BitColumns::parse(&input) {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Bad diagnostic report: {}", e);
+            process::exit(1);
         }
-        println!("gcount = {:?}", gcount);
-        ninputs += 1;
-    }
-    let half_inputs = ninputs / 2;
-    let mut gamma: u16 = 0;
-    
-    for pos in 0..max_bits {
-        let mask = 1 << pos;
-        if gcount[pos] > half_inputs {
-            println!("gamma majority in pos = {}", pos);
-            gamma |= mask;
+    };
+    let gamma = report.gamma();
+    let epsilon = report.epsilon();
+    println!(
+        "ninputs = {} epsilon = {:b} gamma = {:b}",
+        report.values().len(),
+        epsilon,
+        gamma
+    );
+    match gamma.checked_mul(epsilon) {
+        Some(answer) => println!("answer = {}", answer),
+        None => {
+            eprintln!("Power consumption {} * {} is too large", gamma, epsilon);
+            process::exit(1);
         }
     }
-    let mut emask: u16 = 0;
-    for pos in 0..max_bits {
-        let mask = 1 << pos;
-        emask ^= mask;
-    }
-    let epsilon = gamma ^ emask;
-    let answer: i32 = i32::from(epsilon) * i32::from(gamma);
-    println!("ninputs = {} epsilon = {:b} gamma = {:b}", ninputs, epsilon, gamma);
-    println!("answer = {}", answer);
 }
- 
\ No newline at end of file
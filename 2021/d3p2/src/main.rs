@@ -36,49 +36,37 @@ Use the binary numbers in your diagnostic report to calculate the oxygen generat
 
 
 */
-#![feature(stdin_forwarders)]
-use std::io;
+use aocutil::bits::{self, BitColumns, BitsError};
+use std::{io, process};
 
 fn main() {
     println!("Enter submarine diagnostic report:");
-    let dlines: Vec<String> = io::stdin().lines().map(|s| s.unwrap()).collect();
-    let digits: Vec<u16> = dlines.iter().map(|s| {u16::from_str_radix(s.trim(), 2).unwrap()}).collect();
-    let oxygen_rating = bitcrit(&digits, 15, |ones, zeroes| {ones >= zeroes});
-    let co2_rating = bitcrit(&digits, 15, |ones, zeroes| {ones > 0 && ones < zeroes});
-    let lifesupport_rating: u32 = u32::from(oxygen_rating) * u32::from(co2_rating);
-    println!("oxygen_rating = {} co2_rating = {}", oxygen_rating, co2_rating);
-    println!("life support rating = {}", lifesupport_rating);
-}
-
-/* counts 1's at pos in all digits, then passes the # of times 1 was found and the # of elements searched to cmp. if cmp returns true, it keeps all the digits with 1's, otherwise it keeps all the 0's. */
-fn bitcrit<F>(digits: &Vec<u16>, pos: usize, cmp: F) -> u16 
-where F: Fn(usize, usize) -> bool
-{
-    //println!("{} pos = {}", " ".repeat(pos), pos);
-    let mut ones: Vec<u16> = Vec::new();
-    let mut zeroes: Vec<u16> = Vec::new();
-    let mask = 1 << pos;
-    for digit in digits {
-        if digit & mask == mask {
-            ones.push(*digit);
-        } else {
-            zeroes.push(*digit);
+    let input = io::read_to_string(io::stdin()).unwrap();
+    match life_support(&input) {
+        Ok((oxygen_rating, co2_rating)) => {
+            println!(
+                "oxygen_rating = {} co2_rating = {}",
+                oxygen_rating, co2_rating
+            );
+            let Some(rating) = oxygen_rating.checked_mul(co2_rating) else {
+                eprintln!(
+                    "Life support rating {} * {} is too large",
+                    oxygen_rating, co2_rating
+                );
+                process::exit(1);
+            };
+            println!("life support rating = {}", rating);
+        }
+        Err(e) => {
+            eprintln!("Bad diagnostic report: {}", e);
+            process::exit(1);
         }
     }
-    /*
-    println!("ones   = {:?}", ones.iter().map(|n| {format!("{:05b} ", n)}).collect::<String>());
-    println!("zeroes = {:?}", zeroes.iter().map(|n| {format!("{:05b} ", n)}).collect::<String>());
-    */
-    
-    let matches = match cmp(ones.len(), zeroes.len()) {
-        true => ones,
-        false => zeroes,
-    };
-    if matches.len() == 1 {
-        return matches[0];
-    }
-    if pos == 0 {
-        panic!("Maximum bit width exceeded");
-    }
-    return bitcrit(&matches, pos-1, cmp);
+}
+
+fn life_support(input: &str) -> Result<(u128, u128), BitsError> {
+    let report = BitColumns::parse(input)?;
+    let oxygen_rating = report.filter(bits::most_common)?;
+    let co2_rating = report.filter(bits::least_common)?;
+    Ok((oxygen_rating, co2_rating))
 }
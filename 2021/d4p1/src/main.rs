@@ -61,104 +61,34 @@ To guarantee victory against the giant squid, figure out which board will win fi
 
 
 */
-#![feature(stdin_forwarders,str_split_whitespace_as_str)]
-use std::io;
-use std::fmt::{Debug, Formatter, Error};
-
-type Row = Vec<Option<u8>>;
-struct Board(Vec<Row>);
-
-impl Debug for Board {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        for row in self.0.iter() {
-            write!(f, "{}\n", String::from("-").repeat(row.len() * 3))?;
-            for col in row.iter() {
-               match col {
-                   None => write!(f, " X ")?,
-                   Some(v) => write!(f, "{:3}", v)?,
-               };
-            }
-            write!(f, "\n")?;
-        }
-        Ok(())
-    }
-}
+use aocutil::bingo::{Bingo, BingoError};
+use std::{env, io, process};
 
 fn main() {
     println!("Paste bingo output:");
-    let mut first_line = String::new();
-    io::stdin().read_line(&mut first_line).unwrap();
-    io::stdin().read_line(&mut String::new()).unwrap(); // throw one away;
-
-    let called_numbers: Vec<u8> = first_line.split(",").map(|n_str| {n_str.trim().parse::<u8>().unwrap()}).collect();
-    let mut boards: Vec<Board> = Vec::new();
-    let mut this_board: Board = Board(Vec::new());
-    for line in io::stdin().lines() {
-        let line = line.unwrap();
-        if line.trim().len() > 0 {
-            let this_row: Row = line.split_whitespace().map(|n_str| {Some(n_str.parse::<u8>().unwrap())}).collect();
-            this_board.0.push(this_row);
-        } else {
-            println!("---");
-            println!("{:?}", this_board);
-            boards.push(this_board);
-            this_board = Board(Vec::new());
+    let input = io::read_to_string(io::stdin()).unwrap();
+    let diagonals = env::args().any(|arg| arg == "--diagonals");
+    match play(&input, diagonals) {
+        Ok(()) => {}
+        Err(e) => {
+            eprintln!("Bad bingo subsystem output: {}", e);
+            process::exit(1);
         }
     }
-    
-    for called_num in called_numbers.iter() {
-        let mut boardno = 0;
-        for mut board in boards.iter_mut() {
-            mark_board(&mut board, *called_num);
-            match check_board(&board, *called_num) {
-                None => (),
-                Some(answer) => {
-                    println!("{:?}", board);
-                    println!("We found it! Board #{} wins with {}, answer: {}", boardno, called_num, answer);
-                    return;
-                }
-            }
-            boardno += 1;
-        }
-    }
-    panic!("No winners?!");
 }
 
-fn mark_board(board: &mut Board, n: u8){
-    for row in board.0.iter_mut() {
-        for my_n in row.iter_mut() {
-            if match my_n {
-                Some(nval) => *nval == n,
-                None => false,
-            } {
-                *my_n = None;
-                return
-            }
+fn play(input: &str, diagonals: bool) -> Result<(), BingoError> {
+    let bingo = Bingo::parse(input, 5)?.with_diagonals(diagonals);
+    let (wins, boards) = bingo.play_out();
+    match wins.first() {
+        Some(win) => {
+            println!("{}", boards[win.board]);
+            println!(
+                "We found it! Board #{} wins with {} on {:?}, answer: {}",
+                win.board, win.number, win.line, win.score
+            );
         }
+        None => println!("No board ever wins."),
     }
+    Ok(())
 }
-
-fn sum_board(board: &Board) -> u32 {
-    return board.0.iter()
-        .flatten()
-        .filter_map(|v| {
-            match v {
-                None => None,
-                Some(v) => Some(u32::from(*v)),
-            }
-        }).sum()
-}
-
-fn check_board(board: &Board, called_num: u8) -> Option<u32> {
-    for row in board.0.iter() {
-        if row.iter().all(|x| x.is_none()) {
-            return Some(sum_board(board) * u32::from(called_num))
-        }
-    }
-    for col in 0..board.0[0].len() {
-        if board.0.iter().map(|row| row[col]).all(|v| v.is_none()) {
-            return Some(sum_board(board) * u32::from(called_num))
-        }
-    }
-    return None;
-}
\ No newline at end of file
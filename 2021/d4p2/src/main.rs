@@ -70,131 +70,43 @@ In the above example, the second board is the last to win, which happens after 1
 Figure out which board will win last. Once it wins, what would its final score be?
 
 */
-#![feature(stdin_forwarders,str_split_whitespace_as_str)]
-use std::io;
-use std::fmt::{Debug, Formatter, Error};
-use std::collections::HashSet;
-
-type Row = Vec<Option<u8>>;
-struct Board(Vec<Row>);
-
-impl Debug for Board {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        for row in self.0.iter() {
-            write!(f, "{}\n", String::from("-").repeat(row.len() * 3))?;
-            for col in row.iter() {
-               match col {
-                   None => write!(f, " X ")?,
-                   Some(v) => write!(f, "{:3}", v)?,
-               };
-            }
-            write!(f, "\n")?;
-        }
-        Ok(())
-    }
-}
+use aocutil::bingo::{Bingo, BingoError};
+use std::{env, io, process};
 
 fn main() {
     println!("Paste bingo output:");
-    let mut first_line = String::new();
-    io::stdin().read_line(&mut first_line).unwrap();
-    io::stdin().read_line(&mut String::new()).unwrap(); // throw one away;
-
-    let called_numbers: Vec<u8> = first_line.split(",").map(|n_str| {n_str.trim().parse::<u8>().unwrap()}).collect();
-    let mut boards: Vec<Board> = Vec::new();
-    let mut this_board: Board = Board(Vec::new());
-    let mut lines = io::stdin().lines();
-    loop {
-        let line = lines.next();
-        let (store_board, eof) = match line {
-            None => (true, true),
-            Some(line) => {
-                let line = line.unwrap();
-                if line.trim().len() == 0 {
-                    (true, false)
-                } else {
-                    let this_row: Row = line.split_whitespace().map(|n_str| {Some(n_str.parse::<u8>().unwrap())}).collect();
-                    this_board.0.push(this_row);
-                    (false, false)
-                }
-            }
-        };
-        if store_board {
-            println!("---");
-            println!("{:?}", this_board);
-            boards.push(this_board);
-            this_board = Board(Vec::new());
-        }
-        if eof {
-            break;
+    let input = io::read_to_string(io::stdin()).unwrap();
+    let diagonals = env::args().any(|arg| arg == "--diagonals");
+    match play(&input, diagonals) {
+        Ok(()) => {}
+        Err(e) => {
+            eprintln!("Bad bingo subsystem output: {}", e);
+            process::exit(1);
         }
     }
-    println!("We have {} boards.", boards.len());
-    
-    let mut winners: HashSet<usize> = HashSet::new();
-    for called_num in called_numbers.iter() {
-        let mut boardno = 0;
-        let boards_len = boards.len();
-        for mut board in boards.iter_mut() {
-            if !winners.contains(&boardno) {
-                mark_board(&mut board, *called_num);
-                match check_board(&board, *called_num) {
-                    None => (),
-                    Some(answer) => {
-                        println!("Winner! boardno={}\n{:?}", boardno, board);
-                        winners.insert(boardno);
-                        println!("Winners: {:?}", winners);
-                    
-                        if winners.len() == boards_len {
-                            println!("{:?}", board);
-                            println!("The last winner would be {} with {} called, answer={}", boardno, *called_num, answer);
-                            return;
-                        }
-                    }
-                }
-            }
-            boardno += 1;
-        }
-    }
-
-    panic!("Derp?!");
 }
 
-fn mark_board(board: &mut Board, n: u8){
-    for row in board.0.iter_mut() {
-        for my_n in row.iter_mut() {
-            if match my_n {
-                Some(nval) => *nval == n,
-                None => false,
-            } {
-                *my_n = None;
-                return
-            }
+fn play(input: &str, diagonals: bool) -> Result<(), BingoError> {
+    let bingo = Bingo::parse(input, 5)?.with_diagonals(diagonals);
+    println!("We have {} boards.", bingo.boards().len());
+    let (wins, boards) = bingo.play_out();
+    let order: Vec<usize> = wins.iter().map(|win| win.board).collect();
+    println!("Winners: {:?}", order);
+    /* Letting the squid win only works if every board wins eventually,
+    otherwise the last to win isn't the last board standing. */
+    match wins.last() {
+        Some(win) if wins.len() == boards.len() => {
+            println!("{}", boards[win.board]);
+            println!(
+                "The last winner would be {} with {} called on {:?}, answer={}",
+                win.board, win.number, win.line, win.score
+            );
         }
+        _ => println!(
+            "Only {} of {} boards ever win, so there is no last winner.",
+            wins.len(),
+            boards.len()
+        ),
     }
+    Ok(())
 }
-
-fn sum_board(board: &Board) -> u32 {
-    return board.0.iter()
-        .flatten()
-        .filter_map(|v| {
-            match v {
-                None => None,
-                Some(v) => Some(u32::from(*v)),
-            }
-        }).sum()
-}
-
-fn check_board(board: &Board, called_num: u8) -> Option<u32> {
-    for row in board.0.iter() {
-        if row.iter().all(|x| x.is_none()) {
-            return Some(sum_board(board) * u32::from(called_num))
-        }
-    }
-    for col in 0..board.0[0].len() {
-        if board.0.iter().map(|row| row[col]).all(|v| v.is_none()) {
-            return Some(sum_board(board) * u32::from(called_num))
-        }
-    }
-    return None;
-}
\ No newline at end of file
@@ -43,94 +43,31 @@ To avoid the most dangerous areas, you need to determine the number of points wh
 Consider only horizontal and vertical lines. At how many points do at least two lines overlap?
 
 */
-#![feature(stdin_forwarders,str_split_whitespace_as_str)]
-use std::io;
-use std::fmt::{Debug, Formatter, Error};
-use std::cmp::{min,max};
-
-type Row = Vec<u16>;
-struct Board(Vec<Row>);
-#[derive(Debug)]
-struct Coord {
-    x: u16,
-    y: u16,
-}
-#[derive(Debug)]
-struct VentLine {
-    begin: Coord,
-    end: Coord,
-}
-
-impl Debug for Board {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        for row in self.0.iter() {
-            //write!(f, "{}\n", String::from("-").repeat(row.len() * 3))?;
-            for col in row.iter() {
-               write!(f, "{}", match col { 0 => String::from("."), n => format!("{}",n)})?
-            }
-            write!(f, "\n")?;
-        }
-        Ok(())
-    }
-}
+use aocutil::segment::{self, Stepping};
+use std::{env, io, process};
 
 fn main() {
     println!("Paste vent report:");
-    let mut max_x = 0;
-    let mut max_y = 0;
-    let vent_lines: Vec<VentLine> = io::stdin().lines().map(|line| {
-        let line = line.unwrap();
-        let mut tuples = line.split(" -> ");
-        let begin = parse_tuple(tuples.next().unwrap());
-        max_x = max(begin.x, max_x);
-        max_y = max(begin.y, max_y);
-        let end = parse_tuple(tuples.next().unwrap());
-        max_x = max(end.x, max_x);
-        max_y = max(end.y, max_y);
-        VentLine {
-            begin: begin,
-            end: end,
-        }
-    }).collect();
-    // Make the board
-    let mut board = Board(Vec::new());
-    for _row in 0..max_y+1 {
-        board.0.push((0..max_x+1).map(|_| 0).collect::<Row>());
-    }
-    println!("{:?}", board);
-    // Draw lines
-    for vent_line in vent_lines.iter() {
-        if vent_line.begin.x == vent_line.end.x {
-            println!("{:?} is vertical", vent_line);
-            let start_y = min(vent_line.begin.y, vent_line.end.y);
-            let end_y = max(vent_line.begin.y, vent_line.end.y)+1;
-            for row in start_y..end_y {
-                board.0[row as usize][vent_line.begin.x as usize] += 1
-            }
-        } else if vent_line.begin.y == vent_line.end.y {
-            println!("{:?} is horizontal", vent_line);
-            let start_x = min(vent_line.begin.x, vent_line.end.x);
-            let end_x = max(vent_line.begin.x, vent_line.end.x)+1;
-            for col in start_x..end_x {
-                board.0[vent_line.begin.y as usize][col as usize] += 1
-            }
-        } else {
-            println!("{:?} is diagonal", vent_line);
+    let input = io::read_to_string(io::stdin()).unwrap();
+    let vents = match segment::parse_segments(&input) {
+        Ok(vents) => vents,
+        Err(e) => {
+            eprintln!("Bad vent report: {}", e);
+            process::exit(1);
         }
+    };
+    /* Only horizontal and vertical lines count unless --diagonals is given. */
+    let diagonals = env::args().any(|arg| arg == "--diagonals");
+    if env::args().any(|arg| arg == "--show") {
+        let board = segment::rasterize(&vents, diagonals, Stepping::Exact);
+        print!(
+            "{}",
+            board.render(|&n| match n {
+                0 => '.',
+                n => char::from_digit(n.min(9), 10).unwrap(),
+            })
+        );
     }
-    // Print board
-    println!("{:?}", board);
-    let answer = board.0.iter().flatten().filter(|&x| *x >= 2).count();
+    let answer = segment::count_overlaps(&vents, diagonals);
     println!("answer = {:?}", answer);
 }
-
-fn parse_tuple(txt: &str) -> Coord {
-    let ints: Vec<u16> = txt.replace(&['(',')'][..], "")
-                      .split(",")
-                      .map(|s| s.parse::<u16>().unwrap())
-                      .collect();
-    Coord {
-        x: ints[0],
-        y: ints[1]
-    }
-}
\ No newline at end of file
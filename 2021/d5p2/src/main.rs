@@ -70,123 +70,41 @@ Consider all of the lines. At how many points do at least two lines overlap?
 
 
 */
-#![feature(stdin_forwarders, str_split_whitespace_as_str)]
-use std::cmp::{max, min};
-use std::fmt::{Debug, Error, Formatter};
-use std::io;
-
-type Row = Vec<i32>;
-struct Board(Vec<Row>);
-#[derive(Debug)]
-struct Coord {
-    x: i32,
-    y: i32,
-}
-#[derive(Debug)]
-struct VentLine {
-    begin: Coord,
-    end: Coord,
-}
-
-impl Debug for Board {
-    fn fmt(&self, f: &mut Formatter) -> Result<(), Error> {
-        let mut j = 0;
-        for row in self.0.iter() {
-            write!(f, "{:06} ", j);
-            j += 1;
-            for col in row.iter() {
-                write!(
-                    f,
-                    "{}",
-                    match col {
-                        0 => String::from("."),
-                        n => format!("{}", n),
-                    }
-                )?
-            }
-            write!(f, "\n")?;
-        }
-        Ok(())
-    }
-}
+use aocutil::export::{Palette, Raster, Rgb};
+use aocutil::grid::SparseGrid;
+use aocutil::segment::{self, Stepping};
+use std::{env, io, path::Path, process};
 
 fn main() {
     println!("Paste vent report:");
-    let mut max_x = 0;
-    let mut max_y = 0;
-    let vent_lines: Vec<VentLine> = io::stdin()
-        .lines()
-        .map(|line| {
-            let line = line.unwrap();
-            let mut tuples = line.split(" -> ");
-            let begin = parse_tuple(tuples.next().unwrap());
-            max_x = max(begin.x, max_x);
-            max_y = max(begin.y, max_y);
-            let end = parse_tuple(tuples.next().unwrap());
-            max_x = max(end.x, max_x);
-            max_y = max(end.y, max_y);
-            VentLine {
-                begin: begin,
-                end: end,
-            }
-        })
-        .collect();
-    // Make the board
-    let mut board = Board(Vec::new());
-    for _row in 0..max_y + 1 {
-        board.0.push((0..max_x + 2).map(|_| 0).collect::<Row>());
-    }
-    //println!("{:?}", board);
-    println!("Board size is {}x{}", max_x + 1, max_y + 1);
-    println!("Lens are {}x{}", board.0.len(), board.0[0].len());
-    // Draw lines
-    for vent_line in vent_lines.iter() {
-        let start_x = vent_line.begin.x;
-        let end_x = vent_line.end.x;
-        let start_y = vent_line.begin.y;
-        let end_y = vent_line.end.y;
-        let mut row = start_x;
-        let mut col = start_y;
-        let row_step = match end_x - start_x {
-            n if n == 0 => 0,
-            n if n > 0 => 1,
-            _ => -1,
-        };
-        let col_step = match end_y - start_y {
-            n if n == 0 => 0,
-            n if n > 0 => 1,
-            _ => -1,
-        };
-        loop {
-            if row as usize == board.0.len() || col as usize == board.0[row as usize].len() {
-                println!(
-                    "{:?} row={} col={} row_step={} col_step={}",
-                    vent_line, row, col, row_step, col_step
-                );
-            }
-            board.0[row as usize][col as usize] += 1;
-            row += row_step;
-            col += col_step;
-            if row == end_x + row_step && col == end_y + col_step {
-                break;
-            }
+    let input = io::read_to_string(io::stdin()).unwrap();
+    let vents = match segment::parse_segments(&input) {
+        Ok(vents) => vents,
+        Err(e) => {
+            eprintln!("Bad vent report: {}", e);
+            process::exit(1);
         }
-        //println!("{:?}", board);
-    }
-    // Print board
-    //println!("{:?}", board);
-    let answer = board.0.iter().flatten().filter(|&x| *x >= 2).count();
+    };
+    let answer = segment::count_overlaps(&vents, true);
     println!("answer = {:?}", answer);
+    // Optionally save the vent map, e.g. `d5p2 vents.svg < input`
+    if let Some(path) = env::args().nth(1) {
+        // Only points some line touches take up room, so vents can be anywhere
+        let board = segment::rasterize(&vents, true, Stepping::Exact);
+        if let Some(bounds) = board.bounds() {
+            println!("Board size is {}x{}", bounds.width(), bounds.height());
+        }
+        save_board(&board, Path::new(&path)).unwrap();
+        println!("Vent map saved to {}", path);
+    }
 }
 
-fn parse_tuple(txt: &str) -> Coord {
-    let ints: Vec<i32> = txt
-        .replace(&['(', ')'][..], "")
-        .split(",")
-        .map(|s| s.parse::<i32>().unwrap())
-        .collect();
-    Coord {
-        x: ints[0],
-        y: ints[1],
+fn save_board(board: &SparseGrid<u32>, path: &Path) -> io::Result<()> {
+    let palette = Palette::gradient(1..=3, Rgb(40, 40, 120), Rgb(255, 64, 64))
+        .with(0, Rgb::BLACK);
+    let raster = Raster::from_grid(board, &palette);
+    match path.extension().and_then(|e| e.to_str()) {
+        Some("svg") => raster.save_svg(path, 8),
+        _ => raster.save_ppm(path, 8),
     }
 }
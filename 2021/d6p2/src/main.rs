@@ -63,10 +63,10 @@ How many lanternfish would there be after 256 days?
 
 
 */
-#![feature(stdin_forwarders, str_split_whitespace_as_str)]
+use aocutil::recurrence::Recurrence;
 use std::io;
 
-const DAYS: usize = 256;
+const DAYS: u64 = 256;
 
 fn main() {
     println!("Initial line of fish:");
@@ -78,28 +78,21 @@ fn main() {
             .split(",")
             .map(|sint| sint.parse::<usize>().unwrap()),
     );
-    let mut fish_map: Vec<usize> = vec![0; 9];
+    let mut fish_map: Vec<u128> = vec![0; 9];
     fishies.iter().fold(&mut fish_map, |acc, x| {
         acc[*x] += 1;
         acc
     });
-    for i in 0..DAYS {
-        let fish_map_last = fish_map.clone();
-        fish_map = vec![0; 9];
-        for (n_days, day_count) in &mut fish_map_last.iter().enumerate() {
-            if n_days == 0 {
-                println!("zeroes v={}", day_count);
-                fish_map[6] += day_count;
-                fish_map[8] += day_count;
-            } else {
-                fish_map[n_days - 1] += day_count;
-            }
-        }
-        println!("{:?}", fish_map);
-        println!(
-            "After {} days: {}",
-            i,
-            fish_map.iter().fold(0, |acc, v| acc + v)
-        );
-    }
+    // A fish at 0 resets to 6 and spawns a new one at 8, the rest count down.
+    let school = Recurrence::from_rule(9, |timer| match timer {
+        0 => vec![6, 8],
+        timer => vec![timer - 1],
+    });
+    let fish_map = school.advance(&fish_map, DAYS).expect("Too many fish!");
+    println!("{:?}", fish_map);
+    println!(
+        "After {} days: {}",
+        DAYS,
+        fish_map.iter().sum::<u128>()
+    );
 }
@@ -35,24 +35,25 @@ This costs a total of 37 fuel. This is the cheapest possible outcome; more expen
 Determine the horizontal position that the crabs can align to using the least fuel possible. How much fuel must they spend to align to that position?
 
 */
-#![feature(stdin_forwarders, int_abs_diff)]
-use std::io;
+use aocutil::{
+    align::{align, Cost},
+    parse,
+};
+use std::{io, process};
 
 fn main() {
     println!("Initial line of crabs:");
     let mut crabline = String::new();
     io::stdin().read_line(&mut crabline).unwrap();
-    let mut crabs: Vec<usize> = Vec::from_iter(
-        crabline
-            .trim()
-            .split(",")
-            .map(|sint| sint.parse::<usize>().unwrap()),
-    );
-    /* Find the median position */
-    crabs.sort();
-    let half: usize = (crabs.len() as f64 / 2 as f64).ceil() as usize;
-    let median_pos = crabs[half];
-    /* Now add up distance to median */
-    let answer: usize = crabs.iter().fold(0, |acc, x| acc + median_pos.abs_diff(*x) );
-    println!("Pos = {} Answer = {}" , median_pos, answer);
+    let crabs: Vec<i64> = match parse::ints(crabline.trim(), ",") {
+        Ok(crabs) => crabs,
+        Err(e) => {
+            eprintln!("Bad line of crabs: {}", e);
+            process::exit(1);
+        }
+    };
+    match align(&crabs, Cost::Linear) {
+        Some(best) => println!("Pos = {} Answer = {}", best.position, best.cost),
+        None => println!("No crabs to line up."),
+    }
 }
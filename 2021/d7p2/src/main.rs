@@ -58,47 +58,25 @@ This costs a total of 168 fuel. This is the new cheapest possible outcome; the o
 Determine the horizontal position that the crabs can align to using the least fuel possible so they can make you an escape route! How much fuel must they spend to align to that position?
 
 */
-#![feature(stdin_forwarders, int_abs_diff)]
-use std::io;
-use std::collections::HashMap;
+use aocutil::{
+    align::{align, Cost},
+    parse,
+};
+use std::{io, process};
 
 fn main() {
-    let mut cache = HashMap::new();
-    println!("Testing {} to {} = {}", 16, 5, cost_to_move(&mut cache, 16, 5));
     println!("Initial line of crabs:");
     let mut crabline = String::new();
     io::stdin().read_line(&mut crabline).unwrap();
-    let crabs: Vec<usize> = Vec::from_iter(
-        crabline
-            .trim()
-            .split(",")
-            .map(|sint| sint.parse::<usize>().unwrap()),
-    );
-    let min_crab = *crabs.iter().min().unwrap();
-    let max_crab = *crabs.iter().max().unwrap();
-    let unique_crabs: Vec<usize> = (min_crab..max_crab).collect();
-    let mut min_fuel: Option<usize> = None;
-    let mut min_fuel_pos: Option<usize> = None;
-    for unique_crab in unique_crabs.iter() {
-        let mut fuel_cost = 0;
-        for crab in crabs.iter() {
-            fuel_cost += cost_to_move(&mut cache, *crab, *unique_crab);
-        }
-        if fuel_cost < *min_fuel.get_or_insert(fuel_cost) {
-            min_fuel = Some(fuel_cost);
-            min_fuel_pos = Some(*unique_crab);
+    let crabs: Vec<i64> = match parse::ints(crabline.trim(), ",") {
+        Ok(crabs) => crabs,
+        Err(e) => {
+            eprintln!("Bad line of crabs: {}", e);
+            process::exit(1);
         }
+    };
+    match align(&crabs, Cost::Triangular) {
+        Some(best) => println!("Best pos is {} with a cost of {}", best.position, best.cost),
+        None => println!("No crabs to line up."),
     }
-    println!("Best pos is {} with a cost of {}", min_fuel_pos.unwrap(), min_fuel.unwrap());
-}
-
-fn cost_to_move(cache: &mut HashMap<isize, usize>, from: usize, to: usize) -> usize {
-    let abs_diff: isize = from.abs_diff(to).try_into().unwrap();
-    return *cache.entry(abs_diff).or_insert({
-        let mut distance = 0;
-        for i in 1..abs_diff+1 {
-            distance += i;
-        }
-        distance.try_into().unwrap()
-    })
 }
@@ -129,39 +129,15 @@ For each entry, determine all of the wire/segment connections and decode the fou
 
 
 */
-#![feature(stdin_forwarders, int_abs_diff)]
-use std::collections::HashMap;
+use aocutil::solve::{self, Solver};
 use std::io;
 
-use itertools::Itertools;
-
 const PATTERNS: [&str; 10] = [
     "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
 ];
-const UNSCRAMBLED: &str = "abcdefg";
 const N_SEGMENTS: usize = 7;
 
 fn main() {
-    let mut unique_lens: HashMap<usize, usize> = HashMap::new();
-    unique_lens.insert(2, 1);
-    unique_lens.insert(4, 4);
-    unique_lens.insert(3, 7);
-    unique_lens.insert(7, 8);
-    let patterns = Vec::from(PATTERNS);
-    let mut shifted_maps: Vec<HashMap<char, char>> = Vec::with_capacity(N_SEGMENTS * N_SEGMENTS);
-    for p in UNSCRAMBLED
-        .chars()
-        .permutations(N_SEGMENTS)
-        .unique()
-        .filter(|s| !s.iter().any(|c| s.iter().filter(|c2| *c2 == c).count() > 1))
-    {
-        let mut map = HashMap::with_capacity(N_SEGMENTS);
-        let mut unscrambled_walker = UNSCRAMBLED.chars();
-        for rotated_c in p.iter() {
-            map.insert(*rotated_c, unscrambled_walker.next().unwrap());
-        }
-        shifted_maps.push(map);
-    }
     println!("Input notes:");
     let mut final_total = 0;
     for inputline in io::stdin().lines() {
@@ -170,107 +146,52 @@ fn main() {
         let parts: Vec<&str> = inputline.split("|").collect();
         let signals: Vec<&str> = parts[0].trim().split(" ").collect();
         let scrambled_digits: Vec<&str> = parts[1].trim().split(" ").collect();
-        let mut map_cache: HashMap<(Vec<char>, Vec<char>), bool> = HashMap::new();
-        for shifted_map in shifted_maps.iter() {
-            let mapkeys = shifted_map.keys().map(|c| *c).collect::<Vec<char>>();
-            let mapvalues = shifted_map.values().map(|c| *c).collect::<Vec<char>>();
-            let mk = mapkeys.clone();
-            if *map_cache
-                .entry((mapkeys, mapvalues))
-                .or_insert(validate_map(
-                    shifted_map,
-                    &patterns,
-                    &unique_lens,
-                    &signals,
-                    &scrambled_digits,
-                ))
-            {
-                let init = String::from("");
-                print!(
-                    "Hit {}",
-                    mk.iter().fold(init, |started, key| format!(
-                        "{}|{} => {}",
-                        started,
-                        key,
-                        shifted_map.get(key).unwrap()
-                    ))
-                );
-                let line_total = digits_to_total(shifted_map, &patterns, &scrambled_digits);
-                final_total += line_total;
-                println!(" = {}", line_total);
-                break;
-            }
-        }
+        let Some(map) = wiring(&signals, &scrambled_digits) else {
+            println!("No wiring fits this line");
+            continue;
+        };
+        let line_total = scrambled_digits
+            .iter()
+            .fold(0, |total, digit| total * 10 + decode(&map, digit));
+        final_total += line_total;
+        println!(
+            "Hit {} = {}",
+            map.iter()
+                .enumerate()
+                .map(|(wire, segment)| format!("|{} => {}", letter(wire), letter(*segment)))
+                .collect::<String>(),
+            line_total
+        );
     }
     println!("Total for all = {}", final_total);
 }
 
-fn unscramble_digit(map: &HashMap<char, char>, digit: &str) -> String {
-    let mut unscrambled_digit = String::with_capacity(digit.len());
-    for c in digit.chars() {
-        unscrambled_digit.push(*map.get(&c).unwrap())
-    }
-    unscrambled_digit
+fn segments(pattern: &str) -> Vec<usize> {
+    pattern.bytes().map(|b| (b - b'a') as usize).collect()
 }
 
-fn digits_to_total(map: &HashMap<char, char>, patterns: &Vec<&str>, digits: &Vec<&str>) -> usize {
-    let mut elevator = 1000;
-    let mut total = 0;
-    for digit in digits.iter() {
-        let unscrambled_digit = unscramble_digit(map, digit);
-        let value = match patterns.iter().position(|pattern| {
-            pattern.chars().collect::<Vec<char>>()
-                == unscrambled_digit.chars().sorted().collect::<Vec<char>>()
-        }) {
-            None => panic!(
-                "digit = {} unscrambled_digit = {}",
-                digit,
-                unscrambled_digit.chars().sorted().collect::<String>()
-            ),
-            Some(value) => value,
-        };
-        total += value * elevator;
-        elevator = elevator / 10;
-    }
-    total
+fn letter(segment: usize) -> char {
+    (b'a' + segment as u8) as char
 }
 
-fn validate_map(
-    map: &HashMap<char, char>,
-    patterns: &Vec<&str>,
-    unique_lens: &HashMap<usize, usize>,
-    signals: &Vec<&str>,
-    digits: &Vec<&str>,
-) -> bool {
-    // Do the signals all map to real digits?
-    for signal in signals.iter() {
-        let target = unscramble_digit(map, signal);
-        if !patterns.iter().any(|pattern| {
-            pattern.len() == target.len() && pattern.chars().all(|c| target.contains(c))
-        }) {
-            return false;
-        }
-    }
-    // Do the known digits come out right?
-    for digit in digits.iter() {
-        let target = unscramble_digit(map, digit);
-
-        match unique_lens.get(&digit.len()) {
-            None => {
-                if !patterns.iter().any(|pattern| {
-                    pattern.len() == target.len() && pattern.chars().all(|c| target.contains(c))
-                }) {
-                    return false;
-                }
-            }
-            Some(actual) => {
-                if !patterns[*actual].chars().all(|c| target.contains(c)) {
-                    println!("known digit {} not mapping here: {:?}", actual, map);
-                    return false;
-                }
-            }
-        }
+/* Deduces which real segment each scrambled wire drives. Every pattern seen, whether a signal or one of the output digits, has to light up exactly the segments of some digit with the same number of segments. */
+fn wiring(signals: &[&str], digits: &[&str]) -> Option<Vec<usize>> {
+    let mut solver = Solver::new(N_SEGMENTS, N_SEGMENTS);
+    solver.all_different(0..N_SEGMENTS);
+    for pattern in signals.iter().chain(digits) {
+        let options = PATTERNS
+            .iter()
+            .filter(|p| p.len() == pattern.len())
+            .map(|p| solve::domain(segments(p)));
+        solver.image_in(segments(pattern), options);
     }
+    solver.solve()
+}
 
-    true
+fn decode(map: &[usize], digit: &str) -> usize {
+    let lit = solve::domain(segments(digit).into_iter().map(|wire| map[wire]));
+    PATTERNS
+        .iter()
+        .position(|p| solve::domain(segments(p)) == lit)
+        .expect("the wiring only allows real digits")
 }
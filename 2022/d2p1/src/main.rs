@@ -55,115 +55,53 @@ strategy guide?
 
 */
 
+use aocutil::rps::{self, Column, Game, Round};
 use std::fs;
 
+#[cfg(test)]
 const TEST_INPUT: &str = "A Y
 B X
 C Z";
 
-#[derive(Clone,Debug,PartialEq)]
-enum RPSPlay {
-    Rock,
-    Paper,
-    Scissors,
+fn parse_strategy_guide(input: &str) -> Vec<Round> {
+    rps::parse_guide(input, "ABC", "XYZ").expect("Could not parse the guide")
 }
 
-#[derive(PartialEq, Debug)]
-enum RPSResult {
-    Win,
-    Lose,
-    Draw,
+fn score_guide(guide: &[Round]) -> usize {
+    rps::play(&Game::classic(), guide, Column::Shape).expect("every round can be played")
 }
 
-#[derive(Clone, PartialEq, Debug)]
-struct RPSRule {
-    play: RPSPlay,
-    score: usize,
-    winner: RPSPlay,
-}
-
-impl RPSRule {
-    fn result(self: &Self, other: &RPSPlay) -> RPSResult {
-        if self.winner == *other {
-            return RPSResult::Lose
-        }
-        if self.play == *other {
-            return RPSResult::Draw
-        }
-        return RPSResult::Win
-    }
-
-    fn score(self: &Self, other: &RPSPlay) -> usize {
-        return match self.result(other) {
-            RPSResult::Lose => 0,
-            RPSResult::Win => 6,
-            RPSResult::Draw => 3,
-        } + self.score;
-    }
-}
-
-static ROCK: RPSRule = RPSRule {
-    play: RPSPlay::Rock,
-    score: 1,
-    winner: RPSPlay::Paper
-};
-static PAPER: RPSRule = RPSRule {
-    play: RPSPlay::Paper,
-    score: 2,
-    winner: RPSPlay::Scissors
-};
-static SCISSORS: RPSRule = RPSRule {
-    play: RPSPlay::Scissors,
-    score: 3,
-    winner: RPSPlay::Rock,
-};
-
-fn parse_strategy_guide(input: &str) -> Vec<(&'static RPSRule, &'static RPSRule)> {
-    let mut parsed = Vec::new();
-    let lines = input.lines();
-    lines.for_each(|line| {
-        let mut s = line.split(" ");
-        let their_play = match s.next().expect(&format!("Could not parse {}", line)) {
-            "A" => &ROCK,
-            "B" => &PAPER,
-            "C" => &SCISSORS,
-            _ => panic!("Invalid left option: {}", line),
-        };
-        let my_play = match s.next().expect(&format!("Could not parse {}", line)) {
-            "X" => &ROCK,
-            "Y" => &PAPER,
-            "Z" => &SCISSORS, 
-            _ => panic!("Invalid right option: {}", line),
-        };
-        parsed.push((their_play, my_play));
-    });
-    parsed
-}
-
-fn score_guide(guide: Vec<(&'static RPSRule, &'static RPSRule)>) -> usize {
-    guide.iter().map(|(theirs, mine)| mine.score(&theirs.play)).sum()
+/* How a cipher from rps::best_cipher reads, like X=Rock Y=Paper Z=Scissors */
+fn describe_cipher(game: &Game, cipher: &[rps::Shape]) -> String {
+    let reading: Vec<String> = cipher
+        .iter()
+        .zip("XYZ".chars())
+        .map(|(shape, letter)| format!("{}={}", letter, game.name(*shape)))
+        .collect();
+    reading.join(" ")
 }
 
 #[test]
 fn test_parse_strategy_guide() {
-    let test_guide: Vec<(&'static RPSRule, &'static RPSRule)> = vec![
-        (&ROCK, &PAPER),
-        (&PAPER, &ROCK),
-        (&SCISSORS, &SCISSORS),
-    ];
     let parsed_guide = parse_strategy_guide(TEST_INPUT);
-    assert_eq!(test_guide, parsed_guide);
-    let (first_theirs, first_mine) = parsed_guide[0];
-    assert_eq!(first_mine.result(&first_theirs.play), RPSResult::Win);
-    assert_eq!(first_mine.score, 2);
-    assert_eq!(first_mine.score(&first_theirs.play), 8);
-    let (second_theirs, second_mind) = parsed_guide[1];
-    assert_eq!(second_mind.result(&second_theirs.play), RPSResult::Lose);
-    assert_eq!(second_mind.score(&second_theirs.play), 1);
-    assert_eq!(score_guide(parsed_guide), 15)
+    assert_eq!(vec![(0, 1), (1, 0), (2, 2)], parsed_guide);
+    assert_eq!(score_guide(&parsed_guide), 15)
+}
+
+#[test]
+fn test_describe_cipher() {
+    let game = Game::classic();
+    let (cipher, _) = rps::best_cipher(&game, &parse_strategy_guide(TEST_INPUT)).unwrap();
+    assert_eq!("X=Scissors Y=Paper Z=Rock", describe_cipher(&game, &cipher));
 }
 
 fn main() {
     let buf = fs::read_to_string("2022d2p1.txt").unwrap();
-    println!("The total score for this guide played perfectly would be: {}", score_guide(parse_strategy_guide(&buf)));
+    let guide = parse_strategy_guide(&buf);
+    println!("The total score for this guide played perfectly would be: {}", score_guide(&guide));
+    let game = Game::classic();
+    match rps::best_cipher(&game, &guide) {
+        Ok((cipher, best)) => println!("Reading the guide as {} would score {}", describe_cipher(&game, &cipher), best),
+        Err(round) => println!("Round {} can't be read as a cipher", round + 1),
+    }
 }
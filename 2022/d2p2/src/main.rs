@@ -70,120 +70,31 @@ Following the Elf's instructions for the second column, what would your total sc
 
 */
 
+use aocutil::rps::{self, Column, Game, Round};
 use std::fs;
 
+#[cfg(test)]
 const TEST_INPUT: &str = "A Y
 B X
 C Z";
 
-#[derive(Clone,Debug,PartialEq)]
-enum RPSPlay {
-    Rock,
-    Paper,
-    Scissors,
+fn parse_strategy_guide(input: &str) -> Vec<Round> {
+    rps::parse_guide(input, "ABC", "XYZ").expect("Could not parse the guide")
 }
 
-#[derive(PartialEq, Debug)]
-enum RPSResult {
-    Win,
-    Lose,
-    Draw,
-}
-
-#[derive(Clone, PartialEq, Debug)]
-struct RPSRule {
-    play: RPSPlay,
-    score: usize,
-    winner: RPSPlay,
-    loser: RPSPlay,
-}
-
-impl RPSRule {
-    fn result(self: &Self, other: &RPSPlay) -> RPSResult {
-        if self.winner == *other {
-            return RPSResult::Lose
-        }
-        if self.play == *other {
-            return RPSResult::Draw
-        }
-        return RPSResult::Win
-    }
-
-    fn score(self: &Self, other: &RPSPlay) -> usize {
-        return match self.result(other) {
-            RPSResult::Lose => 0,
-            RPSResult::Win => 6,
-            RPSResult::Draw => 3,
-        } + self.score;
-    }
-}
-
-static ROCK: RPSRule = RPSRule {
-    play: RPSPlay::Rock,
-    score: 1,
-    winner: RPSPlay::Paper,
-    loser: RPSPlay::Scissors,
-};
-static PAPER: RPSRule = RPSRule {
-    play: RPSPlay::Paper,
-    score: 2,
-    winner: RPSPlay::Scissors,
-    loser: RPSPlay::Rock
-};
-static SCISSORS: RPSRule = RPSRule {
-    play: RPSPlay::Scissors,
-    score: 3,
-    winner: RPSPlay::Rock,
-    loser: RPSPlay::Paper,
-};
-
-fn play_to_rule(play: &RPSPlay) -> &'static RPSRule {
-    match play {
-        RPSPlay::Rock => &ROCK,
-        RPSPlay::Paper => &PAPER,
-        RPSPlay::Scissors => &SCISSORS,
-    }
-}
-
-fn parse_strategy_guide(input: &str) -> Vec<(&'static RPSRule, RPSPlay)> {
-    let mut parsed = Vec::new();
-    let lines = input.lines();
-    lines.for_each(|line| {
-        let mut s = line.split(" ");
-        let their_play = match s.next().expect(&format!("Could not parse {}", line)) {
-            "A" => &ROCK,
-            "B" => &PAPER,
-            "C" => &SCISSORS,
-            _ => panic!("Invalid left option: {}", line),
-        };
-        let my_play = match s.next().expect(&format!("Could not parse {}", line)) {
-            "X" => their_play.loser.clone(),
-            "Y" => their_play.play.clone(),
-            "Z" => their_play.winner.clone(), 
-            _ => panic!("Invalid right option: {}", line),
-        };
-        parsed.push((their_play, my_play));
-    });
-    parsed
-}
-
-fn score_guide(guide: Vec<(&'static RPSRule, RPSPlay)>) -> usize {
-    guide.iter().map(|(theirs, mine)| play_to_rule(mine).score(&theirs.play)).sum()
+fn score_guide(guide: &[Round]) -> usize {
+    rps::play(&Game::classic(), guide, Column::Outcome).expect("every round can be played")
 }
 
 #[test]
 fn test_parse_strategy_guide() {
-    let test_guide: Vec<(&'static RPSRule, RPSPlay)> = vec![
-        (&ROCK, RPSPlay::Rock),
-        (&PAPER, RPSPlay::Rock),
-        (&SCISSORS, RPSPlay::Rock),
-    ];
     let parsed_guide = parse_strategy_guide(TEST_INPUT);
-    assert_eq!(test_guide, parsed_guide);
-    assert_eq!(score_guide(parsed_guide), 12)
+    assert_eq!(vec![(0, 1), (1, 0), (2, 2)], parsed_guide);
+    assert_eq!(score_guide(&parsed_guide), 12)
 }
 
 fn main() {
     let buf = fs::read_to_string("2022d2p1.txt").unwrap();
-    println!("The total score for this guide played perfectly would be: {}", score_guide(parse_strategy_guide(&buf)));
+    let guide = parse_strategy_guide(&buf);
+    println!("The total score for this guide played perfectly would be: {}", score_guide(&guide));
 }
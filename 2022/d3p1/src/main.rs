@@ -80,10 +80,13 @@ Find the item type that corresponds to the badges of each three-Elf group. What
 
 */
 
-use std::collections::hash_map::RandomState;
-use std::fs;
-use std::collections::HashSet;
+use std::{
+    env,
+    fmt::{self, Formatter},
+    fs, process,
+};
 
+#[cfg(test)]
 const TEST_INPUT: &str = "vJrwpWtwJgWrhcsFMMfFFhFp
 jqHRNqRjqzjGDLGLrsFMfFZSrLrFZsSL
 PmmdzqPrVvPwwTWBwg
@@ -92,72 +95,295 @@ ttgJtRGJQctTZtZT
 CrZsJsPPZsGzwwsLwLmpwMDw
 ";
 
-fn score_byte(b: u8) -> u8 {
-    // lowercase
-    if b >= 97 && b <= 122 {
-        return b - 96
+/* The priority of an item type: a-z are 1-26, A-Z are 27-52. */
+fn priority(b: u8) -> Option<u32> {
+    match b {
+        b'a'..=b'z' => Some((b - b'a') as u32 + 1),
+        b'A'..=b'Z' => Some((b - b'A') as u32 + 27),
+        _ => None,
     }
-    // uppercase
-    if b >= 65 && b <= 90 {
-        return b - 38
+}
+
+/* A set of item types, bit p-1 set for the item with priority p. 52 bits in all. */
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+struct Items(u64);
+
+impl Items {
+    const ALL: Items = Items((1 << 52) - 1);
+
+    fn from_bytes(bytes: &[u8]) -> Result<Items, u8> {
+        bytes
+            .iter()
+            .try_fold(Items(0), |items, &b| match priority(b) {
+                Some(p) => Ok(Items(items.0 | 1 << (p - 1))),
+                None => Err(b),
+            })
+    }
+
+    fn intersect(self, other: Items) -> Items {
+        Items(self.0 & other.0)
+    }
+
+    fn priorities(self) -> impl Iterator<Item = u32> {
+        (1..=52).filter(move |p| self.0 >> (p - 1) & 1 == 1)
+    }
+
+    /* The priority of the one item in the set, or the problem with it. */
+    fn single(self) -> Result<u32, Items> {
+        match self.0.count_ones() {
+            1 => Ok(self.0.trailing_zeros() + 1),
+            _ => Err(self),
+        }
+    }
+}
+
+impl fmt::Display for Items {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for p in self.priorities() {
+            let b = if p <= 26 {
+                b'a' + p as u8 - 1
+            } else {
+                b'A' + p as u8 - 27
+            };
+            write!(f, "{}", b as char)?;
+        }
+        Ok(())
     }
-    panic!("Unknown score for byte = {}", b);
 }
 
-fn parse_inventory(input: &str) -> (Vec<i64>, Vec<i64>) {
-    let mut parsed = Vec::new();
-    let mut group_parsed = Vec::new();
-    let mut lines = input.lines();
-    let mut groupcount = 0;
-    let mut groupset = HashSet::new();
-    loop {
-        let line = lines.next();
-        match line {
-            None => break,
-            Some(line) => {
-                let compartment_len = line.len() / 2;
-                let left_hs: HashSet<u8, RandomState> = HashSet::from_iter(line[0..compartment_len].bytes());
-                let right_hs: HashSet<u8, RandomState> = HashSet::from_iter(line[compartment_len..].bytes());
-                let mistake = left_hs.intersection(&right_hs).next().expect(&format!("No dupes for {}", line));
-                parsed.push(score_byte(*mistake) as i64);
-                if groupcount < 3 {
-                    let this_hs: HashSet<u8, RandomState> = HashSet::from_iter(line.bytes());
-                    if groupset.len() > 0 {
-                        let new_groupset: HashSet<u8, RandomState> = HashSet::from_iter(groupset.intersection(&this_hs).into_iter().map(|b| *b));
-                        groupset = new_groupset;
-                    } else {
-                        groupset = this_hs;
-                    }
-                    groupcount += 1;
-                }
-                if groupcount == 3 {
-                    if groupset.len() > 1 {
-                        panic!("Failed to find one common element at {}", line);
-                    }
-                    group_parsed.push(score_byte(groupset.clone().into_iter().next().unwrap()) as i64);
-                    groupset.clear();
-                    groupcount = 0;
-                }
+/* Something wrong with a rucksack or group of them. Lines are numbered from 1. */
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Problem {
+    BadItem {
+        line: usize,
+        item: char,
+    },
+    OddLength {
+        line: usize,
+        len: usize,
+    },
+    /* The compartments should share exactly one item. */
+    SharedItems {
+        line: usize,
+        shared: Items,
+    },
+    /* The group starting at `line` should have exactly one item in common. */
+    Badges {
+        line: usize,
+        common: Items,
+    },
+    ShortGroup {
+        line: usize,
+        size: usize,
+        wanted: usize,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::BadItem { line, item } => {
+                write!(f, "line {}: {:?} is not an item", line, item)
+            }
+            Problem::OddLength { line, len } => write!(
+                f,
+                "line {}: {} items won't split into two compartments",
+                line, len
+            ),
+            Problem::SharedItems { line, shared } if shared.0 == 0 => {
+                write!(f, "line {}: the compartments share no items", line)
             }
+            Problem::SharedItems { line, shared } => write!(
+                f,
+                "line {}: the compartments share {} items: {}",
+                line,
+                shared.0.count_ones(),
+                shared
+            ),
+            Problem::Badges { line, common } if common.0 == 0 => {
+                write!(f, "group at line {}: no badge in common", line)
+            }
+            Problem::Badges { line, common } => write!(
+                f,
+                "group at line {}: {} possible badges: {}",
+                line,
+                common.0.count_ones(),
+                common
+            ),
+            Problem::ShortGroup { line, size, wanted } => write!(
+                f,
+                "group at line {}: only {} of {} elves",
+                line, size, wanted
+            ),
         }
-    };
-    (parsed, group_parsed)
+    }
+}
+
+/* Each rucksack's items, numbered by line. */
+fn rucksacks(input: &str) -> Vec<(usize, &[u8])> {
+    input
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.as_bytes()))
+        .collect()
+}
+
+fn items(line: usize, bytes: &[u8]) -> Result<Items, Problem> {
+    Items::from_bytes(bytes).map_err(|b| Problem::BadItem {
+        line,
+        item: b as char,
+    })
+}
+
+/* Part one: the priority of the item in both compartments of each rucksack. */
+fn misplaced_items(input: &str) -> Result<Vec<u32>, Vec<Problem>> {
+    collect(rucksacks(input).into_iter().map(|(line, bytes)| {
+        if bytes.len() % 2 != 0 {
+            return Err(Problem::OddLength {
+                line,
+                len: bytes.len(),
+            });
+        }
+        let (left, right) = bytes.split_at(bytes.len() / 2);
+        let shared = items(line, left)?.intersect(items(line, right)?);
+        shared
+            .single()
+            .map_err(|shared| Problem::SharedItems { line, shared })
+    }))
+}
+
+/* Part two: the priority of the one item every elf in a group carries.
+Groups are `size` consecutive rucksacks. */
+fn badges(input: &str, size: usize) -> Result<Vec<u32>, Vec<Problem>> {
+    assert!(size > 0, "groups need at least one elf");
+    let sacks = rucksacks(input);
+    collect(sacks.chunks(size).map(|group| {
+        let line = group[0].0;
+        if group.len() < size {
+            return Err(Problem::ShortGroup {
+                line,
+                size: group.len(),
+                wanted: size,
+            });
+        }
+        let mut common = Items::ALL;
+        for &(line, bytes) in group {
+            common = common.intersect(items(line, bytes)?);
+        }
+        common
+            .single()
+            .map_err(|common| Problem::Badges { line, common })
+    }))
+}
+
+/* Every priority, or every problem if there were any. */
+fn collect<I: Iterator<Item = Result<u32, Problem>>>(results: I) -> Result<Vec<u32>, Vec<Problem>> {
+    let (found, problems): (Vec<_>, Vec<_>) = results.partition(|r| r.is_ok());
+    if problems.is_empty() {
+        Ok(found.into_iter().map(Result::unwrap).collect())
+    } else {
+        Err(problems.into_iter().map(Result::unwrap_err).collect())
+    }
+}
+
+fn report(what: &str, result: Result<Vec<u32>, Vec<Problem>>) {
+    match result {
+        Ok(priorities) => println!(
+            "The total {} would be: {}",
+            what,
+            priorities.iter().sum::<u32>()
+        ),
+        Err(problems) => {
+            println!("No {}, the inventory has problems:", what);
+            for problem in problems {
+                println!("  {}", problem);
+            }
+        }
+    }
 }
 
 #[test]
 fn test_inventory() {
-    let test_inventory: Vec<i64> = vec![16,38,42,22,20,19];
-    let test_group: Vec<i64> = vec![18,52];
-    let (parsed_inventory, parsed_groups) = parse_inventory(TEST_INPUT);
-    assert_eq!(test_inventory, parsed_inventory);
-    assert_eq!(test_group, parsed_groups);
+    let test_inventory: Vec<u32> = vec![16, 38, 42, 22, 20, 19];
+    let test_group: Vec<u32> = vec![18, 52];
+    assert_eq!(Ok(test_inventory), misplaced_items(TEST_INPUT));
+    assert_eq!(Ok(test_group), badges(TEST_INPUT, 3));
+}
+
+#[test]
+fn test_group_sizes() {
+    // All six elves share nothing, while pairs share too much.
+    assert_eq!(
+        Err(vec![Problem::Badges {
+            line: 1,
+            common: Items(0)
+        }]),
+        badges(TEST_INPUT, 6)
+    );
+    let pairs = badges(TEST_INPUT, 2).unwrap_err();
+    assert_eq!(3, pairs.len());
+    assert_eq!(
+        "group at line 1: 5 possible badges: frsFM",
+        pairs[0].to_string()
+    );
+    let fours: Vec<String> = badges(TEST_INPUT, 4)
+        .unwrap_err()
+        .iter()
+        .map(|p| p.to_string())
+        .collect();
+    assert_eq!(
+        vec![
+            "group at line 1: no badge in common",
+            "group at line 5: only 2 of 4 elves"
+        ],
+        fours
+    );
+    // A group of one has a badge if the elf carries a single item type.
+    assert_eq!(Ok(vec![1, 28]), badges("aa\nB\n", 1));
+}
+
+#[test]
+fn test_diagnostics() {
+    let problems = misplaced_items("abca\nabcd\nab1b\nabc\n").unwrap_err();
+    assert_eq!(3, problems.len());
+    assert_eq!(
+        "line 2: the compartments share no items",
+        problems[0].to_string()
+    );
+    assert_eq!("line 3: '1' is not an item", problems[1].to_string());
+    assert_eq!(
+        "line 4: 3 items won't split into two compartments",
+        problems[2].to_string()
+    );
+    let problems = misplaced_items("abAabA\n").unwrap_err();
+    assert_eq!(
+        "line 1: the compartments share 3 items: abA",
+        problems[0].to_string()
+    );
+    assert_eq!(
+        "group at line 1: 2 possible badges: ab",
+        badges("abc\nabd\n", 2).unwrap_err()[0].to_string()
+    );
 }
 
 fn main() {
     let buf = fs::read_to_string("2022d3p1.txt").unwrap();
-    let (parsed_inv, parsed_grp) = parse_inventory(&buf);
-    let total: i64 = parsed_inv.iter().sum();
-    let group_total: i64 = parsed_grp.iter().sum();
-    println!("The total score for this inventory would be: {}", total);
-    println!("The total badge score would be: {}", group_total);
+    /* --group N looks for badges in groups of N elves instead of 3. */
+    let args: Vec<String> = env::args().collect();
+    let size = match args.iter().position(|arg| arg == "--group") {
+        Some(i) => match args
+            .get(i + 1)
+            .and_then(|n| n.parse().ok())
+            .filter(|&n| n > 0)
+        {
+            Some(n) => n,
+            None => {
+                eprintln!("--group needs a positive number");
+                process::exit(1);
+            }
+        },
+        None => 3,
+    };
+    report("score for this inventory", misplaced_items(&buf));
+    report("badge score", badges(&buf, size));
 }
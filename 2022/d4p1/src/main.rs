@@ -63,11 +63,8 @@ In how many assignment pairs do the ranges overlap?
 
 */
 
-use std::collections::hash_map::RandomState;
-use std::error::Error;
+use aocutil::parse::{self, Cursor, ParseError};
 use std::fs;
-use std::collections::HashSet;
-use std::num::ParseIntError;
 
 const TEST_INPUT: &str = "2-4,6-8
 2-3,4-5
@@ -77,19 +74,18 @@ const TEST_INPUT: &str = "2-4,6-8
 2-6,4-8
 ";
 
-fn parse_sections(input: &str) -> Result<Vec<((i32, i32), (i32, i32))>, ParseIntError> {
-    let mut parsed = Vec::new();
-    for line in input.lines() {
-        let (left, right) = line.split_once(',').expect("No comma in line");
-        let (lmin, lmax) = left.split_once('-').expect("No - in left side");
-        let (rmin, rmax) = right.split_once('-').expect("No - in right side");
-        let lmin: i32 = lmin.parse()?;
-        let lmax: i32 = lmax.parse()?;
-        let rmin: i32 = rmin.parse()?;
-        let rmax: i32 = rmax.parse()?;
-        parsed.push(((lmin, lmax), (rmin, rmax)));
-    }
-    Ok(parsed)
+fn parse_range(c: &mut Cursor) -> Result<(i32, i32), ParseError> {
+    let min = c.int()?;
+    c.tag("-")?;
+    Ok((min, c.int()?))
+}
+
+fn parse_sections(input: &str) -> Result<Vec<((i32, i32), (i32, i32))>, ParseError> {
+    parse::lines(input, |c| {
+        let left = parse_range(c)?;
+        c.tag(",")?;
+        Ok((left, parse_range(c)?))
+    })
 }
 
 fn count_overlaps(sections: Vec<((i32,i32),(i32,i32))>) -> i32 {
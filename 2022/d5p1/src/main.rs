@@ -109,12 +109,12 @@ Before the rearrangement process finishes, update your simulation so that the El
 
 */
 
+use aocutil::parse::{self, Cursor, ErrorKind, ParseError};
 use core::fmt;
-use regex::Regex;
 use std::fmt::{Debug, Formatter};
-use std::fs;
-use std::num::ParseIntError;
+use std::{env, fs};
 
+#[cfg(test)]
 const TEST_INPUT: &str = "    [D]    
 [N] [C]    
 [Z] [M] [P]
@@ -142,71 +142,162 @@ impl Debug for Instruction {
     }
 }
 
-fn parse_drawing(input: &str) -> Result<(Vec<Stack>, Vec<Instruction>), ParseIntError> {
-    let mut stacks: Vec<Stack> = Vec::new();
-    let two_halves: Vec<&str> = input.split("\n\n").collect();
-    let stack_string = two_halves[0];
-    // Reverse so column #'s are first
-    let crate_re = Regex::new(r"(\[[A-Z]\])(?:\s|$)").unwrap();
-    for line in stack_string.lines().rev() {
-        if line.contains("0123456789") {
-            continue;
+fn parse_drawing(input: &str) -> Result<(Vec<Stack>, Vec<Instruction>), ParseError> {
+    let (drawing, procedure) = match parse::blocks(input)[..] {
+        [drawing, procedure] => (drawing, procedure),
+        _ => {
+            return Err(Cursor::new(input).error(ErrorKind::Expected(
+                "a drawing and a procedure separated by a blank line".into(),
+            )))
         }
-        for cr in crate_re.find_iter(line) {
-            let col = cr.start() / 4;
-            let crate_char = cr.as_str().chars().nth(1).expect("Should have a char here");
-            loop {
-                if stacks.len() <= col {
-                    stacks.push(Vec::new());
-                } else {
-                    break;
-                }
+    };
+    let drawing = parse::char_grid_padded(drawing.text, ' ');
+    // The bottom row numbers the stacks, and each crate's letter sits in the
+    // same column as its stack's number.
+    let labels = drawing.height - 1;
+    let stacks = (0..drawing.width)
+        .filter(|&x| drawing.get(x, labels).is_some_and(|c| c.is_ascii_digit()))
+        .map(|x| {
+            (0..labels)
+                .rev()
+                .map(|y| drawing.get(x, y).unwrap())
+                .take_while(|c| c.is_ascii_alphabetic())
+                .collect()
+        })
+        .collect();
+    let instructions = procedure.lines(|c| {
+        c.tag("move ")?;
+        let n = c.int()?;
+        c.tag(" from ")?;
+        let from = c.int()?;
+        c.tag(" to ")?;
+        let to = c.int()?;
+        Ok(Instruction { n, from, to })
+    })?;
+    Ok((stacks, instructions))
+}
+
+/* An instruction that can't be carried out, with which step (1-based) it was */
+#[derive(Debug, PartialEq)]
+enum MoveError {
+    NoSuchStack { step: usize, stack: usize },
+    SameStack { step: usize, stack: usize },
+    NotEnoughCrates { step: usize, stack: usize, have: usize, wanted: usize },
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::NoSuchStack { step, stack } => write!(f, "step {}: there is no stack {}", step, stack),
+            MoveError::SameStack { step, stack } => write!(f, "step {}: moving stack {} onto itself", step, stack),
+            MoveError::NotEnoughCrates { step, stack, have, wanted } => {
+                write!(f, "step {}: stack {} has {} crates, can't move {}", step, stack, have, wanted)
             }
-            stacks[col].push(crate_char);
         }
     }
-    let instr_string = two_halves[1];
-    let mut instructions = Vec::new();
-    let instr_re = Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap();
-    for line in instr_string.lines() {
-        let m = instr_re
-            .captures(line)
-            .expect("Instruction line won't parse");
-        let n: usize = m.get(1).unwrap().as_str().parse().unwrap();
-        let from: usize = m.get(2).unwrap().as_str().parse().unwrap();
-        let to: usize = m.get(3).unwrap().as_str().parse().unwrap();
-        instructions.push(Instruction { n, from, to });
-    }
-    Ok((stacks, instructions))
 }
 
-fn apply_instructions(stacks: &mut Vec<Stack>, instructions: &Vec<Instruction>) {
-    for instruction in instructions.iter() {
-        for _i in 0..instruction.n {
-            let this_crate: Crate = stacks[instruction.from as usize - 1]
-                .pop()
-                .expect("Not enough items to move!");
-            stacks[instruction.to as usize - 1].push(this_crate);
+impl Instruction {
+    fn validate(&self, stacks: &[Stack], step: usize) -> Result<(), MoveError> {
+        for stack in [self.from, self.to] {
+            if stack == 0 || stack > stacks.len() {
+                return Err(MoveError::NoSuchStack { step, stack });
+            }
         }
+        if self.from == self.to {
+            return Err(MoveError::SameStack { step, stack: self.from });
+        }
+        let have = stacks[self.from - 1].len();
+        if have < self.n {
+            return Err(MoveError::NotEnoughCrates { step, stack: self.from, have, wanted: self.n });
+        }
+        Ok(())
+    }
+}
+
+trait Crane {
+    /* Takes the top n crates off a stack, in the order they will be set down */
+    fn pick_up(&self, stack: &mut Stack, n: usize) -> Vec<Crate>;
+
+    /* Carries out a validated instruction. Both models can undo a move by making it again in the other direction. */
+    fn apply(&self, stacks: &mut [Stack], from: usize, to: usize, n: usize) {
+        let mut moved = self.pick_up(&mut stacks[from - 1], n);
+        stacks[to - 1].append(&mut moved);
+    }
+}
+
+/* Moves one crate at a time, so a moved pile ends up upside down */
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn pick_up(&self, stack: &mut Stack, n: usize) -> Vec<Crate> {
+        let mut picked = stack.split_off(stack.len() - n);
+        picked.reverse();
+        picked
+    }
+}
+
+/* Moves the whole pile at once */
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn pick_up(&self, stack: &mut Stack, n: usize) -> Vec<Crate> {
+        stack.split_off(stack.len() - n)
     }
 }
 
-fn apply_instructions_9001(stacks: &mut Vec<Stack>, instructions: &Vec<Instruction>) {
-    for instruction in instructions.iter() {
-        let from_stack = &mut stacks[instruction.from - 1];
-        let to_move = from_stack.len() - instruction.n;
-        let mut moved_crates: Vec<Crate> = from_stack.split_off(to_move);
-        let to_stack = &mut stacks[instruction.to - 1];
-        to_stack.append(&mut moved_crates);
+/* Runs a procedure one step at a time, able to go back as well as forward */
+struct Replay<'a, C: Crane> {
+    crane: C,
+    stacks: Vec<Stack>,
+    instructions: &'a [Instruction],
+    done: usize,
+}
+
+impl<'a, C: Crane> Replay<'a, C> {
+    fn new(crane: C, stacks: Vec<Stack>, instructions: &'a [Instruction]) -> Replay<'a, C> {
+        Replay { crane, stacks, instructions, done: 0 }
+    }
+
+    /* Carries out the next instruction. Returns false once there are none left. A bad instruction leaves the stacks alone. */
+    fn step(&mut self) -> Result<bool, MoveError> {
+        let Some(instruction) = self.instructions.get(self.done) else {
+            return Ok(false);
+        };
+        instruction.validate(&self.stacks, self.done + 1)?;
+        self.crane.apply(&mut self.stacks, instruction.from, instruction.to, instruction.n);
+        self.done += 1;
+        Ok(true)
+    }
+
+    /* Takes back the last instruction carried out. Returns false if there wasn't one. */
+    fn undo(&mut self) -> bool {
+        if self.done == 0 {
+            return false;
+        }
+        self.done -= 1;
+        let instruction = &self.instructions[self.done];
+        self.crane.apply(&mut self.stacks, instruction.to, instruction.from, instruction.n);
+        true
+    }
+
+    fn run(&mut self) -> Result<(), MoveError> {
+        while self.step()? {}
+        Ok(())
+    }
+
+    fn stacks(&self) -> &[Stack] {
+        &self.stacks
+    }
+
+    fn into_stacks(self) -> Vec<Stack> {
+        self.stacks
     }
 }
 
 #[test]
 fn test_instructions() {
-    let mut test_stacks: Vec<Stack> = Vec::new();
-    test_stacks.push(vec!['Z', 'N']);
-    test_stacks.push(vec!['M', 'C', 'D']);
-    test_stacks.push(vec!['P']);
+    let test_stacks: Vec<Stack> = vec![vec!['Z', 'N'], vec!['M', 'C', 'D'], vec!['P']];
     let test_instructions: Vec<Instruction> = vec![
         Instruction {
             n: 1,
@@ -229,38 +320,90 @@ fn test_instructions() {
             to: 2,
         },
     ];
-    let test_stacks = test_stacks;
-    let (mut parsed_stacks, instructions) = parse_drawing(TEST_INPUT).unwrap();
+    let (parsed_stacks, instructions) = parse_drawing(TEST_INPUT).unwrap();
     assert_eq!(test_stacks, parsed_stacks);
     assert_eq!(test_instructions, instructions);
-    let mut moved_test_stacks: Vec<Stack> = Vec::new();
-    moved_test_stacks.push(vec!['C']);
-    moved_test_stacks.push(vec!['M']);
-    moved_test_stacks.push(vec!['P', 'D', 'N', 'Z']);
-    let mut save_parsed_stacks = parsed_stacks.clone();
-    apply_instructions(&mut parsed_stacks, &instructions);
-    assert_eq!(moved_test_stacks, parsed_stacks);
-    let mut moved_9001_stacks: Vec<Stack> = Vec::new();
-    moved_9001_stacks.push(vec!['M']);
-    moved_9001_stacks.push(vec!['C']);
-    moved_9001_stacks.push(vec!['P', 'Z', 'N', 'D']);
-    apply_instructions_9001(&mut save_parsed_stacks, &instructions);
-    assert_eq!(moved_9001_stacks, save_parsed_stacks);
+    let moved_test_stacks: Vec<Stack> = vec![vec!['C'], vec!['M'], vec!['P', 'D', 'N', 'Z']];
+    let mut replay = Replay::new(CrateMover9000, parsed_stacks.clone(), &instructions);
+    replay.run().unwrap();
+    assert_eq!(moved_test_stacks, replay.stacks());
+    assert_eq!("CMZ", top_stacks(replay.stacks()));
+    let moved_9001_stacks: Vec<Stack> = vec![vec!['M'], vec!['C'], vec!['P', 'Z', 'N', 'D']];
+    let mut replay = Replay::new(CrateMover9001, parsed_stacks.clone(), &instructions);
+    replay.run().unwrap();
+    assert_eq!(moved_9001_stacks, replay.stacks());
+    assert_eq!("MCD", top_stacks(replay.stacks()));
+    // Undoing everything gets back to the drawing.
+    while replay.undo() {}
+    assert_eq!(parsed_stacks, replay.stacks());
+    assert_eq!(TEST_INPUT.split("\n\n").next().unwrap(), render_stacks(replay.stacks()).trim_end_matches('\n'));
+}
+
+#[test]
+fn test_bad_moves() {
+    let (stacks, _) = parse_drawing(TEST_INPUT).unwrap();
+    let bad = vec![
+        Instruction { n: 2, from: 1, to: 3 },
+        Instruction { n: 3, from: 1, to: 2 },
+    ];
+    let mut replay = Replay::new(CrateMover9000, stacks.clone(), &bad);
+    assert_eq!(Ok(true), replay.step());
+    assert_eq!(Err(MoveError::NotEnoughCrates { step: 2, stack: 1, have: 0, wanted: 3 }), replay.step());
+    assert_eq!("step 2: stack 1 has 0 crates, can't move 3", replay.run().unwrap_err().to_string());
+    assert!(replay.undo());
+    assert!(!replay.undo());
+    assert_eq!(stacks, replay.stacks());
+    let nowhere = [Instruction { n: 1, from: 2, to: 4 }];
+    assert_eq!(Err(MoveError::NoSuchStack { step: 1, stack: 4 }), Replay::new(CrateMover9001, stacks.clone(), &nowhere).run());
+    let in_place = [Instruction { n: 1, from: 2, to: 2 }];
+    assert_eq!(Err(MoveError::SameStack { step: 1, stack: 2 }), Replay::new(CrateMover9001, stacks, &in_place).run());
 }
 
-fn top_stacks(stacks: &mut Vec<Stack>) -> String {
+/* The crate on top of each stack, or a space for an empty one */
+fn top_stacks(stacks: &[Stack]) -> String {
     stacks
-        .iter_mut()
-        .map(|stack| stack.pop().unwrap())
+        .iter()
+        .map(|stack| stack.last().copied().unwrap_or(' '))
         .collect::<String>()
 }
 
+/* Draws the stacks the way the puzzle input does, labels and all */
+fn render_stacks(stacks: &[Stack]) -> String {
+    let height = stacks.iter().map(|stack| stack.len()).max().unwrap_or(0);
+    let mut out = String::new();
+    for level in (0..height).rev() {
+        let row: Vec<String> = stacks
+            .iter()
+            .map(|stack| match stack.get(level) {
+                Some(c) => format!("[{}]", c),
+                None => String::from("   "),
+            })
+            .collect();
+        out.push_str(&row.join(" "));
+        out.push('\n');
+    }
+    let labels: Vec<String> = (1..=stacks.len()).map(|n| format!("{:^3}", n)).collect();
+    out.push_str(&labels.join(" "));
+    out.push('\n');
+    out
+}
+
 fn main() {
     let buf = fs::read_to_string("2022d5p1.txt").unwrap();
-    let (mut parsed_stacks, instructions) = parse_drawing(&buf).unwrap();
-    let mut saved_parsed_stacks = parsed_stacks.clone();
-    apply_instructions(&mut parsed_stacks, &instructions);
-    println!("The answer is: {}", top_stacks(&mut parsed_stacks));
-    apply_instructions_9001(&mut saved_parsed_stacks, &instructions);
-    println!("9001 Answer is: {}", top_stacks(&mut saved_parsed_stacks));
+    let (stacks, instructions) = parse_drawing(&buf).unwrap();
+    let mut replay = Replay::new(CrateMover9000, stacks, &instructions);
+    match replay.run() {
+        Ok(()) => println!("The answer is: {}", top_stacks(replay.stacks())),
+        Err(e) => println!("CrateMover 9000 got stuck: {}", e),
+    }
+    // Wind back to the starting drawing for the other crane.
+    while replay.undo() {}
+    let mut replay = Replay::new(CrateMover9001, replay.into_stacks(), &instructions);
+    match replay.run() {
+        Ok(()) => println!("9001 Answer is: {}", top_stacks(replay.stacks())),
+        Err(e) => println!("CrateMover 9001 got stuck: {}", e),
+    }
+    if env::args().any(|arg| arg == "--show") {
+        print!("{}", render_stacks(replay.stacks()));
+    }
 }
@@ -35,54 +35,37 @@ How many characters need to be processed before the first start-of-packet marker
 
 */
 
+use aocutil::window;
 use std::fs;
 
-fn has_dupes(input: &str) -> bool {
-    let mut seen = Vec::with_capacity(input.len());
-    for c in input.chars() {
-        if seen.contains(&c) {
-            return true;
-        }
-        seen.push(c);
-    }
-    return false
-}
-
-fn find_undup_packet(input: &str, winsize: usize) -> usize {
-    let mut window = &input[0..winsize];
-    let mut at = 0;
-    for (pos, _c) in input.chars().enumerate() {
-        if !has_dupes(window) {
-            break;
-        }
-        at = pos + winsize;
-        window = &input[pos..at];
-    }
-    at
+fn find_start_packet(input: &str) -> Option<usize> {
+    window::first_distinct_bytes(input.as_bytes(), 4)
 }
 
-fn find_start_packet(input: &str) -> usize {
-    find_undup_packet(input, 4)
-}
-
-fn find_start_message(input: &str) -> usize {
-    find_undup_packet(input, 14)
+fn find_start_message(input: &str) -> Option<usize> {
+    window::first_distinct_bytes(input.as_bytes(), 14)
 }
 
 #[test]
 fn test_signals() {
-    assert_eq!(find_start_packet("bvwbjplbgvbhsrlpgdmjqwftvncz"), 5);
-    assert_eq!(find_start_packet("nppdvjthqldpwncqszvftbrmjlhg"), 6);
-    assert_eq!(find_start_packet("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 10);
-    assert_eq!(find_start_packet("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 11);
-    assert_eq!(find_start_message("bvwbjplbgvbhsrlpgdmjqwftvncz"), 23);
-    assert_eq!(find_start_message("nppdvjthqldpwncqszvftbrmjlhg"), 23);
-    assert_eq!(find_start_message("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), 29);
-    assert_eq!(find_start_message("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), 26);
+    assert_eq!(find_start_packet("bvwbjplbgvbhsrlpgdmjqwftvncz"), Some(5));
+    assert_eq!(find_start_packet("nppdvjthqldpwncqszvftbrmjlhg"), Some(6));
+    assert_eq!(find_start_packet("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), Some(10));
+    assert_eq!(find_start_packet("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), Some(11));
+    assert_eq!(find_start_message("bvwbjplbgvbhsrlpgdmjqwftvncz"), Some(23));
+    assert_eq!(find_start_message("nppdvjthqldpwncqszvftbrmjlhg"), Some(23));
+    assert_eq!(find_start_message("nznrnfrfntjfmvfwmzdfjlvtqnbhcprsg"), Some(29));
+    assert_eq!(find_start_message("zcfzfwzzqfrljwzlrfnpqdbhtmscgvjw"), Some(26));
 }
 
 fn main() {
     let buf = fs::read_to_string("2022d6p1.txt").unwrap();
-    println!("Position for packet is: {}", find_start_packet(&buf));
-    println!("Position for message is: {}", find_start_message(&buf));
+    match find_start_packet(&buf) {
+        Some(at) => println!("Position for packet is: {}", at),
+        None => println!("No packet marker in the signal"),
+    }
+    match find_start_message(&buf) {
+        Some(at) => println!("Position for message is: {}", at),
+        None => println!("No message marker in the signal"),
+    }
 }
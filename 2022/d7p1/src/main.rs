@@ -102,55 +102,240 @@ Find the smallest directory that, if deleted, would free up enough space on the
 
 */
 
-use std::{fs, path::{PathBuf, Path}, collections::{HashMap, BinaryHeap}, cmp::Reverse};
+use std::{env, fs, fmt};
 
-const CD: &'static str = "$ cd ";
-const LS: &'static str = "$ ls";
+const CD: &str = "$ cd ";
+const LS: &str = "$ ls";
 
+#[derive(Debug, PartialEq)]
+enum Kind {
+    /* Children in the order they were listed, and whether ls has been run here yet */
+    Dir { children: Vec<usize>, listed: bool },
+    File { size: usize },
+}
+
+#[derive(Debug, PartialEq)]
+struct Node {
+    name: String,
+    parent: Option<usize>,
+    kind: Kind,
+}
+
+/* Something in the transcript that can't be true of any filesystem, with the (1-based) line it was found on */
+#[derive(Debug, PartialEq)]
+enum TranscriptError {
+    UnknownCommand { line: usize, text: String },
+    BadEntry { line: usize, text: String },
+    NotADirectory { line: usize, path: String },
+    /* A second ls of a directory disagreed with the first */
+    Inconsistent { line: usize, path: String, detail: String },
+}
+
+impl fmt::Display for TranscriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranscriptError::UnknownCommand { line, text } => write!(f, "line {}: unknown command {:?}", line, text),
+            TranscriptError::BadEntry { line, text } => write!(f, "line {}: can't read listing entry {:?}", line, text),
+            TranscriptError::NotADirectory { line, path } => write!(f, "line {}: {} is not a directory", line, path),
+            TranscriptError::Inconsistent { line, path, detail } => write!(f, "line {}: listing of {} disagrees: {}", line, path, detail),
+        }
+    }
+}
+
+/* The directory tree a terminal transcript reveals. Node 0 is / and nodes refer to each other by index. */
+#[derive(Debug)]
+struct Filesystem {
+    nodes: Vec<Node>,
+    /* Directories that were listed more than once, consistently */
+    relisted: Vec<String>,
+}
 
-fn parse_cmds<'a> (buf: &'a str, cwd: &'a mut PathBuf) -> Vec<(String, &'a str, usize)> {
-    let mut files: Vec<(String, &'a str, usize)>  = Vec::new();
-    for line in buf.lines() {
-        if line.starts_with(CD) {
-            let (_cmd, target) = line.rsplit_once(' ').expect("no space in cd cmd");
-            if target == ".." {
-                cwd.pop();
-            } else {
-                cwd.push(target);
+impl Filesystem {
+    fn new() -> Filesystem {
+        Filesystem {
+            nodes: vec![Node { name: String::from("/"), parent: None, kind: Kind::Dir { children: Vec::new(), listed: false } }],
+            relisted: Vec::new(),
+        }
+    }
+
+    fn from_transcript(input: &str) -> Result<Filesystem, TranscriptError> {
+        let mut fs = Filesystem::new();
+        let mut cwd = 0;
+        // Entries seen so far in the listing being read, if any, and the line it started on
+        let mut listing: Option<(usize, Vec<usize>)> = None;
+        for (n, text) in input.lines().enumerate() {
+            let line = n + 1;
+            if text.starts_with('$') {
+                if let Some((start, seen)) = listing.take() {
+                    fs.end_listing(cwd, start, seen)?;
+                }
+                if let Some(target) = text.strip_prefix(CD) {
+                    cwd = match target {
+                        "/" => 0,
+                        ".." => fs.nodes[cwd].parent.unwrap_or(0),
+                        name => fs.child_dir(cwd, name, line)?,
+                    };
+                } else if text == LS {
+                    listing = Some((line, Vec::new()));
+                } else {
+                    return Err(TranscriptError::UnknownCommand { line, text: text.to_string() });
+                }
+                continue;
             }
-            continue;
+            let Some((_, seen)) = listing.as_mut() else {
+                return Err(TranscriptError::UnknownCommand { line, text: text.to_string() });
+            };
+            let (size, name) = text.split_once(' ').ok_or_else(|| TranscriptError::BadEntry { line, text: text.to_string() })?;
+            let kind = match size {
+                "dir" => Kind::Dir { children: Vec::new(), listed: false },
+                size => Kind::File { size: size.parse().map_err(|_| TranscriptError::BadEntry { line, text: text.to_string() })? },
+            };
+            seen.push(fs.add(cwd, name, kind, line)?);
         }
-        if line.starts_with(LS) || line.starts_with("dir") {
-            continue;
+        if let Some((start, seen)) = listing {
+            fs.end_listing(cwd, start, seen)?;
         }
-        // Assuming all else is files
-        let (size, path) = line.split_once(' ').expect("No space found in line");
-        let size: usize = size.parse().expect(&format!("[{}] size({}) did not parse as number", line, size));
-        files.push((String::from(cwd.as_path().clone().to_str().unwrap()), path, size));
+        Ok(fs)
     }
-    files
-}
 
-fn sum_one_dir<'a>(sums: &mut HashMap<&'a str, usize>, path: &'a Path, size: usize) {
-    sums.entry(path.to_str().unwrap()).and_modify(|s: &mut usize| *s += size).or_insert(size);
-    match path.parent() {
-        None => return,
-        Some(path) => sum_one_dir(sums, path, size),
+    fn children(&self, dir: usize) -> &[usize] {
+        match &self.nodes[dir].kind {
+            Kind::Dir { children, .. } => children,
+            Kind::File { .. } => &[],
+        }
     }
-}
 
-fn sum_dirs<'a>(input: &'a Vec<(String, &'a str, usize)>) -> HashMap<&'a str, usize> {
-    let mut sums = HashMap::new();
-    for (dir, _file, size) in input.iter() {
-        let p = Path::new(dir);
-        sum_one_dir(&mut sums, p, *size);
+    fn lookup(&self, dir: usize, name: &str) -> Option<usize> {
+        self.children(dir).iter().copied().find(|&child| self.nodes[child].name == name)
+    }
+
+    /* cd into a directory that hasn't been listed yet still works, it just shows up in the tree */
+    fn child_dir(&mut self, dir: usize, name: &str, line: usize) -> Result<usize, TranscriptError> {
+        let child = match self.lookup(dir, name) {
+            Some(child) => child,
+            None => self.insert(dir, name, Kind::Dir { children: Vec::new(), listed: false }),
+        };
+        match self.nodes[child].kind {
+            Kind::Dir { .. } => Ok(child),
+            Kind::File { .. } => Err(TranscriptError::NotADirectory { line, path: self.path(child) }),
+        }
+    }
+
+    /* Adds a listed entry, or checks it against the one already there */
+    fn add(&mut self, dir: usize, name: &str, kind: Kind, line: usize) -> Result<usize, TranscriptError> {
+        let Some(existing) = self.lookup(dir, name) else {
+            return Ok(self.insert(dir, name, kind));
+        };
+        let detail = match (&self.nodes[existing].kind, &kind) {
+            (Kind::Dir { .. }, Kind::Dir { .. }) => None,
+            (Kind::File { size: was }, Kind::File { size }) if was == size => None,
+            (Kind::File { size: was }, Kind::File { size }) => Some(format!("{} was {} bytes, now {}", name, was, size)),
+            (Kind::Dir { .. }, Kind::File { .. }) => Some(format!("{} was a directory, now a file", name)),
+            (Kind::File { .. }, Kind::Dir { .. }) => Some(format!("{} was a file, now a directory", name)),
+        };
+        match detail {
+            None => Ok(existing),
+            Some(detail) => Err(TranscriptError::Inconsistent { line, path: self.path(dir), detail }),
+        }
+    }
+
+    fn insert(&mut self, dir: usize, name: &str, kind: Kind) -> usize {
+        let id = self.nodes.len();
+        self.nodes.push(Node { name: name.to_string(), parent: Some(dir), kind });
+        if let Kind::Dir { children, .. } = &mut self.nodes[dir].kind {
+            children.push(id);
+        }
+        id
+    }
+
+    /* A listing is over: a repeat must have shown exactly what the first one did */
+    fn end_listing(&mut self, dir: usize, line: usize, seen: Vec<usize>) -> Result<(), TranscriptError> {
+        let path = self.path(dir);
+        let Kind::Dir { children, listed } = &mut self.nodes[dir].kind else {
+            unreachable!("only directories are ever the cwd");
+        };
+        if !*listed {
+            *listed = true;
+            return Ok(());
+        }
+        if let Some(&missing) = children.iter().find(|child| !seen.contains(child)) {
+            let detail = format!("{} is missing", self.nodes[missing].name);
+            return Err(TranscriptError::Inconsistent { line, path, detail });
+        }
+        self.relisted.push(path);
+        Ok(())
+    }
+
+    fn path(&self, node: usize) -> String {
+        match self.nodes[node].parent {
+            None => String::from("/"),
+            Some(0) => format!("/{}", self.nodes[node].name),
+            Some(parent) => format!("{}/{}", self.path(parent), self.nodes[node].name),
+        }
+    }
+
+    fn find_path(&self, path: &str) -> Option<usize> {
+        path.split('/').filter(|part| !part.is_empty()).try_fold(0, |dir, name| self.lookup(dir, name))
+    }
+
+    /* The total size of every node, files included, in one pass. Children always come after their parent, so adding each node into its parent from the back totals everything. */
+    fn sizes(&self) -> Vec<usize> {
+        let mut sizes: Vec<usize> = self.nodes.iter().map(|node| match node.kind {
+            Kind::File { size } => size,
+            Kind::Dir { .. } => 0,
+        }).collect();
+        for node in (1..self.nodes.len()).rev() {
+            let parent = self.nodes[node].parent.expect("only / has no parent");
+            sizes[parent] += sizes[node];
+        }
+        sizes
+    }
+
+    fn du(&self, path: &str) -> Option<usize> {
+        self.find_path(path).map(|node| self.sizes()[node])
+    }
+
+    /* Every directory whose total size passes keep, with that size */
+    fn find<F>(&self, keep: F) -> Vec<(String, usize)>
+    where
+        F: Fn(usize) -> bool,
+    {
+        let sizes = self.sizes();
+        (0..self.nodes.len())
+            .filter(|&node| matches!(self.nodes[node].kind, Kind::Dir { .. }) && keep(sizes[node]))
+            .map(|node| (self.path(node), sizes[node]))
+            .collect()
+    }
+
+    /* The smallest directory that frees up enough space when deleted */
+    fn to_delete(&self, total_space: usize, needed_space: usize) -> Option<(String, usize)> {
+        let used = self.sizes()[0];
+        let at_least = needed_space.saturating_sub(total_space.saturating_sub(used));
+        self.find(|size| size >= at_least).into_iter().min_by_key(|(_path, size)| *size)
+    }
+
+    fn tree(&self) -> String {
+        let mut out = String::new();
+        self.render(0, 0, &mut out);
+        out
+    }
+
+    fn render(&self, node: usize, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        match self.nodes[node].kind {
+            Kind::File { size } => out.push_str(&format!("{}- {} (file, size={})\n", indent, self.nodes[node].name, size)),
+            Kind::Dir { .. } => {
+                out.push_str(&format!("{}- {} (dir)\n", indent, self.nodes[node].name));
+                for &child in self.children(node) {
+                    self.render(child, depth + 1, out);
+                }
+            }
+        }
     }
-    sums
 }
 
-#[test]
-fn test_parse_cmds() {
-    let test_input = "$ cd /
+#[cfg(test)]
+const TEST_INPUT: &str = "$ cd /
 $ ls
 dir a
 14848514 b.txt
@@ -173,28 +358,59 @@ $ ls
 8033020 d.log
 5626152 d.ext
 7214296 k";
-    let root = String::from("/");
-    let a = String::from("/a");
-    let ae = String::from("/a/e");
-    let d = String::from("/d");
-
-    let test_findings: Vec<(String, &str, usize)> = vec![
-        (root.clone(), "b.txt", 14848514),
-        (root, "c.dat", 8504156),
-        (a.clone(), "f", 29116),
-        (a.clone(), "g", 2557),
-        (a, "h.lst", 62596),
-        (ae, "i", 584),
-        (d.clone(), "j", 4060174),
-        (d.clone(), "d.log", 8033020),
-        (d.clone(), "d.ext", 5626152),
-        (d, "k", 7214296),
-    ];
-    let mut cwd = PathBuf::from("/");
-    let parsed_cmds = parse_cmds(test_input, &mut cwd);
-    assert_eq!(test_findings, parsed_cmds);
-    let sums = sum_dirs(&parsed_cmds);
-    assert_eq!(48381165, *sums.get("/").unwrap());
+
+#[test]
+fn test_parse_cmds() {
+    let fs = Filesystem::from_transcript(TEST_INPUT).unwrap();
+    assert_eq!("- / (dir)
+  - a (dir)
+    - e (dir)
+      - i (file, size=584)
+    - f (file, size=29116)
+    - g (file, size=2557)
+    - h.lst (file, size=62596)
+  - b.txt (file, size=14848514)
+  - c.dat (file, size=8504156)
+  - d (dir)
+    - j (file, size=4060174)
+    - d.log (file, size=8033020)
+    - d.ext (file, size=5626152)
+    - k (file, size=7214296)
+", fs.tree());
+    assert_eq!(Some(48381165), fs.du("/"));
+    assert_eq!(Some(94853), fs.du("/a"));
+    assert_eq!(Some(584), fs.du("/a/e"));
+    assert_eq!(Some(24933642), fs.du("/d"));
+    assert_eq!(Some(62596), fs.du("/a/h.lst"));
+    assert_eq!(None, fs.du("/a/x"));
+    let small = fs.find(|size| size <= 100000);
+    assert_eq!(vec![(String::from("/a"), 94853), (String::from("/a/e"), 584)], small);
+    assert_eq!(95437, small.iter().map(|(_path, size)| size).sum::<usize>());
+    assert_eq!(Some((String::from("/d"), 24933642)), fs.to_delete(TOTAL_SPACE, NEEDED_SPACE));
+}
+
+#[test]
+fn test_repeated_listings() {
+    let again = format!("{}\n$ cd /\n$ ls\n8504156 c.dat\ndir a\ndir d\n14848514 b.txt", TEST_INPUT);
+    let fs = Filesystem::from_transcript(&again).unwrap();
+    assert_eq!(vec![String::from("/")], fs.relisted);
+    assert_eq!(Some(48381165), fs.du("/"));
+
+    let changed = format!("{}\n$ cd ..\n$ cd a\n$ ls\ndir e\n29116 f\n2558 g\n62596 h.lst", TEST_INPUT);
+    assert_eq!(
+        "line 29: listing of /a disagrees: g was 2557 bytes, now 2558",
+        Filesystem::from_transcript(&changed).unwrap_err().to_string()
+    );
+    let shrunk = format!("{}\n$ cd ..\n$ cd a\n$ ls\ndir e\n29116 f", TEST_INPUT);
+    assert_eq!(
+        "line 26: listing of /a disagrees: g is missing",
+        Filesystem::from_transcript(&shrunk).unwrap_err().to_string()
+    );
+    let into_file = format!("{}\n$ cd k", TEST_INPUT);
+    assert_eq!(
+        Err(TranscriptError::NotADirectory { line: 24, path: String::from("/d/k") }),
+        Filesystem::from_transcript(&into_file).map(|_| ())
+    );
 }
 
 const TOTAL_SPACE: usize = 70000000;
@@ -202,15 +418,32 @@ const NEEDED_SPACE: usize = 30000000;
 
 fn main() {
     let buf = fs::read_to_string("2022d7p1.txt").unwrap();
-    let mut cwd = PathBuf::from("/");
-    let parsed_cmds = parse_cmds(&buf, &mut cwd);
-    let summed_dirs = sum_dirs(&parsed_cmds);
-    summed_dirs.iter().filter(|(_dir, size)| **size <= 100000).for_each(|(dir, size)| println!("Remove {} which has size {}", dir, size));
-    println!("The total is: {}", summed_dirs.iter().filter(|(dir, size)| **size <= 100000 && **dir != "/").fold(0, |t, (_dir, size)| t + size));
-    let total_used = summed_dirs.get("/").unwrap();
-    let unused_space = TOTAL_SPACE - total_used;
-    let at_least = NEEDED_SPACE - unused_space;
-    let mut suitable_dirs: BinaryHeap<Reverse<usize>> = summed_dirs.iter().map(|(_dir, size)| *size).filter(|size| *size > at_least).map(Reverse).collect();
-    let target_size: Reverse<usize> = suitable_dirs.pop().unwrap();
-    println!("Removing {:?} bytes", target_size);
+    let filesystem = match Filesystem::from_transcript(&buf) {
+        Ok(filesystem) => filesystem,
+        Err(e) => {
+            println!("Bad transcript: {}", e);
+            return;
+        }
+    };
+    // --tree prints the whole tree, --du <path> the size of one path
+    let args: Vec<String> = env::args().collect();
+    if args.iter().any(|arg| arg == "--tree") {
+        print!("{}", filesystem.tree());
+    }
+    if let Some(path) = args.iter().skip_while(|arg| *arg != "--du").nth(1) {
+        match filesystem.du(path) {
+            Some(size) => println!("{}\t{}", size, path),
+            None => println!("{} not found", path),
+        }
+    }
+    for path in filesystem.relisted.iter() {
+        println!("{} was listed more than once", path);
+    }
+    let small_dirs = filesystem.find(|size| size <= 100000);
+    small_dirs.iter().for_each(|(dir, size)| println!("Remove {} which has size {}", dir, size));
+    println!("The total is: {}", small_dirs.iter().map(|(_dir, size)| size).sum::<usize>());
+    match filesystem.to_delete(TOTAL_SPACE, NEEDED_SPACE) {
+        Some((dir, size)) => println!("Removing {} frees {} bytes", dir, size),
+        None => println!("Even deleting everything doesn't free enough space"),
+    }
 }
@@ -73,8 +73,8 @@ Consider each tree on your map. What is the highest scenic score possible for an
 
 */
 
-use std::{fs, cmp::max, fmt::{Formatter, Error, self}, collections::{HashMap, hash_map::{OccupiedEntry, VacantEntry}}};
-use itertools::iproduct;
+use std::{env, fs, cmp::max, fmt::{Formatter, Error, self}};
+use aocutil::{grid::Bounds, viz::{Cell, Colour, Frame}};
 
 
 #[derive(PartialEq)]
@@ -110,154 +110,89 @@ fn parse_grove(input: &str) -> Grove {
     }
 }
 
-const LEFT: (isize,isize) = (-1,0);
-const RIGHT: (isize,isize) = (1, 0);
-const UP: (isize,isize) = (0, -1);
-const DOWN: (isize,isize) = (0, 1);
+/* What every tree can see: whether it is visible from outside the grove, and its scenic score. Both are indexed like Grove::grid. */
+struct Sightlines {
+    width: usize,
+    visible: Vec<bool>,
+    scores: Vec<usize>,
+}
+
+impl Sightlines {
+    fn is_visible(&self, x: usize, y: usize) -> bool {
+        self.visible[y*self.width+x]
+    }
+
+    fn scenic_score(&self, x: usize, y: usize) -> usize {
+        self.scores[y*self.width+x]
+    }
+}
 
 impl Grove {
     fn get_tree(&self, x: usize, y:usize) -> u8 {
-        return self.grid[y*self.width+x]
+        self.grid[y*self.width+x]
     }
 
-    fn is_visible(&self, x: usize, y:usize) -> bool {
-        // println!("Checking ({},{})", x, y);
-        let this_tree = self.get_tree(x,y);
-        // left
-        let mut left_visible = true;
-        for look_x in (0..=x-1).rev() {
-            if self.get_tree(look_x, y) >= this_tree {
-                left_visible = false;
-                break
-            }
+    /* Looks along every row and column in both directions, keeping a stack of the trees that could still block the view: each one taller than everything after it. A new tree pops the shorter trees it hides, and whatever is left on top is the first tree at least as tall, or nothing if it can see the edge. Every tree goes on and off each stack once, so this is O(width·height). */
+    fn sightlines(&self) -> Sightlines {
+        let mut lines = Sightlines {
+            width: self.width,
+            visible: vec![false; self.grid.len()],
+            scores: vec![1; self.grid.len()],
+        };
+        let rows = (0..self.height).map(|y| (0..self.width).map(|x| y*self.width+x).collect::<Vec<_>>());
+        let columns = (0..self.width).map(|x| (0..self.height).map(|y| y*self.width+x).collect::<Vec<_>>());
+        for line in rows.chain(columns) {
+            self.look_along(line.iter().copied(), &mut lines);
+            self.look_along(line.iter().rev().copied(), &mut lines);
         }
-        // right
-        let mut right_visible = true;
-        for look_x in x+1..self.width {
-            if self.get_tree(look_x, y) >= this_tree {
-                right_visible = false;
-                break;
-            }
-        }
-        // up
-        let mut up_visible = true;
-        for look_y in (0..=y-1).rev() {
-            if self.get_tree(x, look_y) >= this_tree {
-                up_visible = false;
-                break;
-            }
-        }
-        // down
-        let mut down_visible = true;
-        for look_y in y+1..self.height {
-            if self.get_tree(x, look_y) >= this_tree {
-                down_visible = false;
-                break;
-            }
-        }
-        left_visible | right_visible | up_visible | down_visible
+        lines
     }
 
-    fn scenic_score(&self, x: usize, y: usize) -> usize {
-        let this_tree = self.get_tree(x, y);
-        // left
-        let mut left_score = 0;
-        if x > 0 {
-            for look_x in (0..x).rev() {
-                left_score += 1;
-                if self.get_tree(look_x, y) >= this_tree {
-                    break;
-                }
+    fn look_along(&self, line: impl Iterator<Item = usize>, lines: &mut Sightlines) {
+        // (position along the line, height)
+        let mut blockers: Vec<(usize, u8)> = Vec::new();
+        for (pos, i) in line.enumerate() {
+            let tree = self.grid[i];
+            while blockers.last().is_some_and(|&(_, height)| height < tree) {
+                blockers.pop();
             }
-        }
-        // right
-        let mut right_score = 0;
-        if x < self.width {
-            for look_x in x+1..self.width { 
-                right_score += 1;
-                if self.get_tree(look_x, y) >= this_tree {
-                    break;
+            match blockers.last() {
+                None => {
+                    lines.visible[i] = true;
+                    lines.scores[i] *= pos;
                 }
+                Some(&(blocker, _)) => lines.scores[i] *= pos - blocker,
             }
+            blockers.push((pos, tree));
         }
-        // up
-        let mut up_score = 0;
-        if y > 0 {
-            for look_y in (0..=y-1).rev() {
-                up_score += 1;
-                if self.get_tree(x, look_y) >= this_tree {
-                    break;
-                }
-            }
-        }
-        // down
-        let mut down_score = 0;
-        if y < self.height {
-            for look_y in y+1..self.height {
-                down_score += 1;
-                if self.get_tree(x, look_y) >= this_tree {
-                    break;
-                }
-            }
-        }
-        left_score * right_score * up_score * down_score
     }
 
     fn visible_trees(&self) -> usize {
-        // Count the outer most ring
-        let mut outer_visible_trees = (2*self.width) + (2*(self.height-2));
-        let mut visible_trees: HashMap<(usize,usize), bool> = HashMap::with_capacity(self.grid.len());
-        // starting at the next inner ring
-        // for each ring
-        for ring in 1..=self.width / 2 {
-            let mut x = ring;
-            let mut y = ring;
-        //   for each tree moving clockwise through the ring
-            //  first row
-            let x_bound = self.width - (ring * 2);
-            let y_bound = self.height - (ring * 2);
-            println!("ring = {} x_bound = {} y_bound = {}", ring, x_bound, y_bound);
-            for x_offset in 0..x_bound {
-                let pos = (x+x_offset, y);
-                visible_trees.entry(pos)
-                        .and_modify(|value| println!("Encountered {:?} another time = {}", pos, value))
-                        .or_insert(self.is_visible(pos.0, pos.1));
-            }
-            // println!("Right column");
-            for y_offset in 1..y_bound {
-                let pos = (x+x_bound-1, y+y_offset);
-                visible_trees.entry(pos)
-                    .and_modify(|value| println!("Encountered {:?} another time = {}", pos, value))
-                    .or_insert(self.is_visible(pos.0, pos.1));
-            }
-            // println!("Bottom Row");
-            for x_offset in 0..x_bound-1 {
-                let pos = (x+x_offset, y+y_bound-1);
-                visible_trees.entry(pos)
-                    .and_modify(|value| println!("Encountered {:?} another time = {}", pos, value))
-                    .or_insert(self.is_visible(pos.0, pos.1));
-            }
-            // println!("Left Column");
-            for y_offset in 1..y_bound-1 {
-                let pos = (x, y+y_offset);
-                visible_trees.entry(pos)
-                    .and_modify(|value| println!("Encountered {:?} another time = {}", pos, value))
-                    .or_insert(self.is_visible(pos.0, pos.1));
+        self.sightlines().visible.iter().filter(|v| **v).count()
+    }
+
+    /* Tree heights, with the trees visible from outside the grove in green */
+    fn frame(&self) -> Frame {
+        let bounds = Bounds { min_x: 0, min_y: 0, max_x: self.width as i64 - 1, max_y: self.height as i64 - 1 };
+        let lines = self.sightlines();
+        Frame::from_fn(bounds, |(x, y)| {
+            let (x, y) = (x as usize, y as usize);
+            let glyph = char::from_digit(self.get_tree(x, y) as u32, 10).unwrap();
+            if lines.is_visible(x, y) {
+                Cell::coloured(glyph, Colour::Green)
+            } else {
+                Cell::coloured(glyph, Colour::Grey)
             }
-        }
-        visible_trees.iter().filter(|(_k, v)| **v).count() + outer_visible_trees
+        })
     }
 
     fn best_scenic_score(&self) -> usize {
-        iproduct!(0..self.width, 0..self.height)
-            .map(|(x, y)| {
-                let s = self.scenic_score(x, y);
-                if s > self.width.pow(4) {
-                    println!("{},{} = {}", x, y, s)
-                }
-                s
-            })
-            .max().unwrap()
+        let lines = self.sightlines();
+        (0..self.height)
+            .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+            .map(|(x, y)| lines.scenic_score(x, y))
+            .max()
+            .unwrap_or(0)
     }
 }
 
@@ -285,24 +220,46 @@ fn test_parse_grid() {
     let parsed_grove = parse_grove(test_input);
     println!("{:?}", parsed_grove);
     assert_eq!(test_grove, parsed_grove);
-    assert!(parsed_grove.is_visible(1,1));
-    assert!(parsed_grove.is_visible(2,1));
-    assert!(!parsed_grove.is_visible(3,1));
-    assert!(parsed_grove.is_visible(1,2));
-    assert!(!parsed_grove.is_visible(2,2));
-    assert!(parsed_grove.is_visible(3,2));
-    assert!(parsed_grove.is_visible(2,3));
-    assert!(!parsed_grove.is_visible(1,3));
-    assert!(!parsed_grove.is_visible(3,3));
+    let sightlines = parsed_grove.sightlines();
+    assert!(sightlines.is_visible(1,1));
+    assert!(sightlines.is_visible(2,1));
+    assert!(!sightlines.is_visible(3,1));
+    assert!(sightlines.is_visible(1,2));
+    assert!(!sightlines.is_visible(2,2));
+    assert!(sightlines.is_visible(3,2));
+    assert!(sightlines.is_visible(2,3));
+    assert!(!sightlines.is_visible(1,3));
+    assert!(!sightlines.is_visible(3,3));
     assert_eq!(21, parsed_grove.visible_trees());
-    assert_eq!(4, parsed_grove.scenic_score(2,1));
-    assert_eq!(8, parsed_grove.scenic_score(2,3));
+    let frame = parsed_grove.frame();
+    assert_eq!(format!("{}\n", test_input), frame.to_string());
+    assert_eq!(Some(Cell::coloured('5', Colour::Green)), frame.get((1, 1)));
+    assert_eq!(Some(Cell::coloured('1', Colour::Grey)), frame.get((3, 1)));
+    assert_eq!(4, sightlines.scenic_score(2,1));
+    assert_eq!(8, sightlines.scenic_score(2,3));
     assert_eq!(8, parsed_grove.best_scenic_score());
+    // Every edge tree is visible and has a score of 0.
+    for i in 0..5 {
+        for (x, y) in [(i, 0), (i, 4), (0, i), (4, i)] {
+            assert!(sightlines.is_visible(x, y));
+            assert_eq!(0, sightlines.scenic_score(x, y));
+        }
+    }
+}
+
+#[test]
+fn test_single_tree() {
+    let grove = parse_grove("7");
+    assert_eq!(1, grove.visible_trees());
+    assert_eq!(0, grove.best_scenic_score());
 }
 
 fn main() {
     let buf = fs::read_to_string("2022d8p1.txt").unwrap();
     let grove = parse_grove(&buf);
+    if env::args().any(|arg| arg == "--show") {
+        print!("{}", grove.frame().render(true));
+    }
     println!("Grove has {} visible trees.", grove.visible_trees());
     println!("The best scenic score possible is {}", grove.best_scenic_score());
 }
\ No newline at end of file
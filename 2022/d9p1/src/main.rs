@@ -249,39 +249,125 @@ Simulate your complete hypothetical series of motions. How many positions does t
 
 */
 
-use std::{fs, collections::HashSet};
+use aocutil::{grid::Bounds, viz::{Cell, Colour, Frame, Player}};
+use std::{env, fs, process, collections::HashSet};
 
-#[derive(Debug)]
+#[cfg(test)]
+const TEST_INPUT: &str = "R 4
+U 4
+L 3
+D 1
+R 4
+D 1
+L 5
+R 2";
+
+/* A rope of knots, knots[0] being the head. Each knot follows the one in front of it, and the positions visited by the tracked knot are remembered. */
+#[derive(Debug, Clone)]
 struct Rope {
-    head: Coordinates,
-    tail: Coordinates,
-    tail_positions: HashSet<Coordinates>,
+    knots: Vec<Coordinates>,
+    tracked: usize,
+    visited: HashSet<Coordinates>,
 }
 
 impl Rope {
-    fn new() -> Rope {
+    /* A rope of n knots tracking its tail */
+    fn new(n: usize) -> Rope {
+        assert!(n > 0, "a rope needs at least one knot");
+        Rope::tracking(n, n - 1)
+    }
+
+    fn tracking(n: usize, tracked: usize) -> Rope {
+        assert!(tracked < n, "knot {} is not on a rope of {}", tracked, n);
+        let start = Coordinates { x: 0, y: 0 };
         Rope {
-            head: Coordinates {
-                x: 0,
-                y: 0,
-            },
-            tail: Coordinates {
-                x: 0,
-                y: 0,
-            },
-            tail_positions: HashSet::new(),
+            knots: vec![start; n],
+            tracked,
+            visited: HashSet::from([start]),
         }
     }
 
+    fn head(&self) -> Coordinates {
+        self.knots[0]
+    }
+
+    fn tail(&self) -> Coordinates {
+        self.knots[self.knots.len() - 1]
+    }
+
     fn adjust(&mut self, adjustment: Adjustment) {
-        for i in 0..adjustment.distance {
-            self.head.step(adjustment.direction);
-            self.tail.step(self.head.pull(self.tail));
-            self.tail_positions.insert(self.tail);
+        for _ in 0..adjustment.distance {
+            self.knots[0].step(adjustment.direction);
+            for i in 1..self.knots.len() {
+                let (leader, knot) = (self.knots[i - 1], self.knots[i]);
+                if leader.touching(knot) {
+                    // Nothing further back can move either.
+                    break;
+                }
+                self.knots[i].step(leader.pull(knot));
+            }
+            self.visited.insert(self.knots[self.tracked]);
         }
     }
+
+    /* The whole trail of visited positions with the rope on top */
+    fn frame(&self) -> Frame {
+        let mut bounds = Bounds::around(screen(Coordinates{x: 0, y: 0}));
+        self.visited.iter().for_each(|c| bounds.include(screen(*c)));
+        self.draw(bounds)
+    }
+
+    /* A window of the given size on screen that follows the head */
+    fn view(&self, width: i64, height: i64) -> Frame {
+        let (x, y) = screen(self.head());
+        self.draw(Bounds {
+            min_x: x - width / 2,
+            min_y: y - height / 2,
+            max_x: x - width / 2 + width - 1,
+            max_y: y - height / 2 + height - 1,
+        })
+    }
+
+    fn draw(&self, bounds: Bounds) -> Frame {
+        let trail: HashSet<(i64, i64)> = self.visited.iter().map(|c| screen(*c)).collect();
+        let mut frame = Frame::from_fn(bounds, |p| Cell::plain(if trail.contains(&p) { '#' } else { '.' }));
+        frame.set(screen(Coordinates{x: 0, y: 0}), Cell::plain('s'));
+        // Back to front, so knots in front are drawn over the ones behind.
+        let last = self.knots.len() - 1;
+        for (i, knot) in self.knots.iter().enumerate().rev() {
+            let cell = match i {
+                0 => Cell::coloured('H', Colour::Red),
+                i if i == last => Cell::coloured('T', Colour::Yellow),
+                i => Cell::coloured(char::from_digit(i as u32 % 10, 10).unwrap(), Colour::Cyan),
+            };
+            frame.set(screen(*knot), cell);
+        }
+        frame
+    }
 }
 
+/* Up is +y here but down the screen, so rows are drawn flipped */
+fn screen(c: Coordinates) -> (i64, i64) {
+    (c.x as i64, -c.y as i64)
+}
+
+/* One frame per instruction, following the head, for playing back */
+fn record(instructions: &[Adjustment], knots: usize) -> Vec<Frame> {
+    let mut rope = Rope::new(knots);
+    let mut frames = vec![rope.view(41, 21).with_caption("== Initial State ==")];
+    for adjustment in instructions {
+        rope.adjust(*adjustment);
+        let caption = format!("== {:?} {} ==", adjustment.direction, adjustment.distance);
+        frames.push(rope.view(41, 21).with_caption(caption));
+    }
+    frames
+}
+
+fn simulate(instructions: &[Adjustment], knots: usize) -> Rope {
+    let mut rope = Rope::new(knots);
+    instructions.iter().for_each(|adjustment| rope.adjust(*adjustment));
+    rope
+}
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 struct Coordinates {
@@ -292,8 +378,8 @@ struct Coordinates {
 impl Coordinates {
     fn step(&mut self, direction: Direction) {
         match direction {
-            Direction::Down => self.y += 1,
-            Direction::Up => self.y -= 1,
+            Direction::Down => self.y -= 1,
+            Direction::Up => self.y += 1,
             Direction::Left => self.x -= 1,
             Direction::Right => self.x += 1,
             Direction::DownRight => { self.step(Direction::Down); self.step(Direction::Right)},
@@ -304,6 +390,10 @@ impl Coordinates {
         };
     }
 
+    fn touching(&self, other: Coordinates) -> bool {
+        (self.x - other.x).abs() <= 1 && (self.y - other.y).abs() <= 1
+    }
+
     /* Returns the direction other would need to move to get closer to self */
     fn pull(&self, other: Coordinates) -> Direction {
         let x = self.x - other.x;
@@ -435,14 +525,6 @@ fn parse_instructions(input: &str) -> Vec<Adjustment> {
 
 #[test]
 fn test_parse_instructions() {
-    let test_input = "R 4
-U 4
-L 3
-D 1
-R 4
-D 1
-L 5
-R 2";
     let test_instructions = vec![
         Adjustment{direction: Direction::Right, distance: 4},
         Adjustment{direction: Direction::Up, distance: 4},
@@ -453,15 +535,103 @@ R 2";
         Adjustment{direction: Direction::Left, distance: 5},
         Adjustment{direction: Direction::Right, distance: 2},
     ];
-    let parsed_instructions = parse_instructions(&test_input);
+    let parsed_instructions = parse_instructions(TEST_INPUT);
     assert_eq!(test_instructions, parsed_instructions);
-    let mut rope = Rope::new();
-    parsed_instructions.iter().for_each(|adjustment| rope.adjust(*adjustment));
-    assert_eq!(13, rope.tail_positions.len());
-    
+    let rope = simulate(&parsed_instructions, 2);
+    assert_eq!(13, rope.visited.len());
+    assert_eq!("..##.
+...##
+.TH##
+....#
+s###.
+", rope.frame().to_string());
+}
+
+#[test]
+fn test_ten_knots() {
+    let rope = simulate(&parse_instructions(TEST_INPUT), 10);
+    assert_eq!(1, rope.visited.len());
+    let larger = parse_instructions("R 5
+U 8
+L 8
+D 3
+R 17
+D 10
+L 25
+U 20");
+    let rope = simulate(&larger, 10);
+    assert_eq!(36, rope.visited.len());
+    assert_eq!(Coordinates{x: -11, y: 15}, rope.head());
+    assert_eq!(Coordinates{x: -11, y: 6}, rope.tail());
+    // The second knot follows the head just like a two knot rope's tail.
+    let mut second = Rope::tracking(10, 1);
+    larger.iter().for_each(|adjustment| second.adjust(*adjustment));
+    assert_eq!(simulate(&larger, 2).visited, second.visited);
+}
+
+#[test]
+fn test_record() {
+    let frames = record(&parse_instructions(TEST_INPUT), 2);
+    assert_eq!(9, frames.len());
+    let first = frames[1].to_string();
+    assert!(first.starts_with("== Right 4 ==\n"));
+    // The head sits in the middle of the window, the tail just behind it.
+    assert_eq!(Some(Cell::coloured('H', Colour::Red)), frames[1].get((4, 0)));
+    assert_eq!(Some(Cell::coloured('T', Colour::Yellow)), frames[1].get((3, 0)));
+    assert_eq!(Bounds { min_x: -16, min_y: -10, max_x: 24, max_y: 10 }, frames[1].bounds());
+}
+
+#[test]
+fn test_rope_cycle() {
+    // Play the example motions on repeat. Where we are in the list and the
+    // tail's offset from the head decide everything; the head just drifts.
+    let instructions = parse_instructions(TEST_INPUT);
+    let step = |(i, rope): &(usize, Rope)| {
+        let mut rope = rope.clone();
+        rope.adjust(instructions[*i]);
+        ((i + 1) % instructions.len(), rope)
+    };
+    let shape = |(i, rope): &(usize, Rope)| {
+        (*i, rope.tail().x - rope.head().x, rope.tail().y - rope.head().y)
+    };
+    let found = aocutil::cycle::detect((0, Rope::new(2)), step, shape, 1000).unwrap();
+    assert_eq!(8, found.cycle.length);
+    let brent = aocutil::cycle::brent((0, Rope::new(2)), step, shape, 1000);
+    assert_eq!(Some(found.cycle), brent);
+
+    let mut state = (0, Rope::new(2));
+    for _ in 0..1001 {
+        state = step(&state);
+    }
+    let head_x = |(_, rope): &(usize, Rope)| rope.head().x as i128;
+    let head_y = |(_, rope): &(usize, Rope)| rope.head().y as i128;
+    let tail_y = |(_, rope): &(usize, Rope)| rope.tail().y as i128;
+    assert_eq!(head_x(&state), found.extrapolate(1001, head_x));
+    assert_eq!(tail_y(&state), found.extrapolate(1001, tail_y));
+    // Each pass through the list moves the head 2 right and 2 up.
+    let passes = 1_000_000_000_000;
+    assert_eq!(2 * passes as i128, found.extrapolate(8 * passes, head_x));
+    assert_eq!(2 * passes as i128, found.extrapolate(8 * passes, head_y));
 }
 
 fn main() {
-    let buf = fs::read_to_string("2022d8p1.txt").unwrap();
-    println!("Directions: {:?}", parse_instructions(&buf));
+    let buf = fs::read_to_string("2022d9p1.txt").unwrap();
+    let instructions = parse_instructions(&buf);
+    let show = env::args().any(|arg| arg == "--show");
+    /* --play steps through the 10 knot rope move by move */
+    if env::args().any(|arg| arg == "--play") {
+        if let Err(e) = Player::new(record(&instructions, 10)).play() {
+            eprintln!("Playback failed: {}", e);
+            process::exit(1);
+        }
+        return;
+    }
+    for knots in [2, 10] {
+        let rope = simulate(&instructions, knots);
+        if show {
+            print!("{}", rope.frame().render(true));
+        }
+        println!("Positions visited by the tail of {} knots: {}", knots, rope.visited.len());
+        println!("Head ended at {:?}, tail at {:?}", rope.head(), rope.tail());
+    }
 }
\ No newline at end of file
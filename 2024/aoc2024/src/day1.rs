@@ -74,53 +74,24 @@ Once again consider your left and right lists. What is their similarity score?
 
  */
 
-use std::{
-    cmp::{max, min, Reverse},
-    collections::{BinaryHeap, HashMap},
-    fs::File,
-    path::PathBuf,
+use std::{fs::File, path::PathBuf};
+
+use aocutil::{
+    multiset::Multiset,
+    parse::{Cursor, ParseError},
 };
 
-use crate::util::parse_file;
-
-fn day1p1(nums: &Vec<(u32, u32)>) {
-    let lefts: BinaryHeap<_> = nums
-        .iter()
-        .map(|(left, _right)| *left)
-        .map(Reverse)
-        .collect();
-    let mut rights: BinaryHeap<_> = nums
-        .iter()
-        .map(|(_left, right)| *right)
-        .map(Reverse)
-        .collect();
-    let answer: u32 = lefts
-        .into_sorted_vec()
-        .iter()
-        .rev()
-        .map(|left| {
-            let left = left.0;
-            let right = rights.pop().expect("Equal num of lefts and rights").0;
-            let distance = max(left, right) - min(left, right);
-            distance
-        })
-        .sum();
-    println!("sum of distances = {}", answer);
+use crate::util::parse_input;
+
+fn day1p1(lefts: &Multiset<u32>, rights: &Multiset<u32>) {
+    match lefts.pairing_distance(rights, |left, right| left.abs_diff(*right) as u64) {
+        Ok(answer) => println!("sum of distances = {}", answer),
+        Err(e) => println!("no sum of distances: {}", e),
+    }
 }
 
-fn day1p2(nums: &Vec<(u32, u32)>) {
-    let lefts = nums.iter().map(|(left, _right)| left);
-    let rights: HashMap<u32, u32> =
-        nums.iter()
-            .map(|(_left, right)| right)
-            .fold(HashMap::new(), |mut hmap, val| {
-                let counter = hmap.entry(*val).or_insert(0);
-                *counter += 1;
-                hmap
-            });
-    let similarity = lefts.fold(0, |score, left| {
-        score + (left * rights.get(left).unwrap_or(&0))
-    });
+fn day1p2(lefts: &Multiset<u32>, rights: &Multiset<u32>) {
+    let similarity = lefts.similarity(rights, |&id| id as u64);
     println!("similarity = {}", similarity);
 }
 
@@ -130,14 +101,27 @@ pub(crate) fn day1(input: Option<PathBuf>) {
         Some(infile) => File::open(infile),
     };
     println!("data = {:?}", data);
-    let nums = parse_file(data.unwrap(), parser);
-    day1p1(&nums);
-    day1p2(&nums);
+    let rows = parse_input(data.unwrap(), parser).expect("day 1 input should parse");
+    let lefts: Multiset<u32> = rows.iter().filter_map(|(left, _right)| *left).collect();
+    let rights: Multiset<u32> = rows.iter().filter_map(|(_left, right)| *right).collect();
+    day1p1(&lefts, &rights);
+    day1p2(&lefts, &rights);
+}
+
+/// One row of the two lists. Either column can be blank when one list ran
+/// out before the other.
+fn parser(line: &mut Cursor) -> Result<(Option<u32>, Option<u32>), ParseError> {
+    let left = match line.peek() {
+        Some(c) if c.is_ascii_digit() => Some(line.int()?),
+        _ => None,
+    };
+    line.skip_whitespace();
+    let right = if line.is_empty() { None } else { Some(line.int()?) };
+    Ok((left, right))
 }
 
-fn parser(line: String) -> (u32, u32) {
-    let mut parts = line.split("   ");
-    let left: u32 = parts.next().unwrap().parse().unwrap();
-    let right: u32 = parts.next().unwrap().parse().unwrap();
-    (left, right)
+#[test]
+fn test_uneven_columns() {
+    let rows = aocutil::parse::lines("3   4\n4\n    5", parser).unwrap();
+    assert_eq!(vec![(Some(3), Some(4)), (Some(4), None), (None, Some(5))], rows);
 }
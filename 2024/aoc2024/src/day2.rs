@@ -39,92 +39,231 @@ Analyze the unusual data from the engineers. How many reports are safe?
 
 use std::{fs::File, path::PathBuf};
 
-use crate::util::parse_file;
+use crate::{trace::Trace, util::parse_file};
 
-pub fn day2(path: Option<PathBuf>) {
+fn reports(path: Option<PathBuf>) -> Vec<Vec<i32>> {
     let input = match path {
         None => File::open("day2test.txt"),
         Some(path) => File::open(path),
     };
-    let nums = parse_file(input.unwrap(), parser);
+    parse_file(input.unwrap(), parser)
+}
+
+/// What counts as a safe step between two adjacent levels.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rule {
+    pub min_step: i32,
+    pub max_step: i32,
+}
+
+/// The reactor's rule: levels change by 1 to 3 each time.
+pub const RULE: Rule = Rule {
+    min_step: 1,
+    max_step: 3,
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+}
+
+impl Direction {
+    /// The direction a report heads in if it starts `from` then `to`.
+    fn of(from: i32, to: i32) -> Direction {
+        if to > from {
+            Direction::Increasing
+        } else {
+            Direction::Decreasing
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Violation {
+    WrongDirection,
+    TooSmall,
+    TooBig,
+}
+
+impl Rule {
+    /// Checks one step of a report that is heading in `direction`.
+    pub fn check(&self, from: i32, to: i32, direction: Direction) -> Result<(), Violation> {
+        let step = match direction {
+            Direction::Increasing => to - from,
+            Direction::Decreasing => from - to,
+        };
+        if step < 0 {
+            Err(Violation::WrongDirection)
+        } else if step < self.min_step {
+            Err(Violation::TooSmall)
+        } else if step > self.max_step {
+            Err(Violation::TooBig)
+        } else {
+            Ok(())
+        }
+    }
+
+    fn allows(&self, from: i32, to: i32, direction: Direction) -> bool {
+        self.check(from, to, direction).is_ok()
+    }
+}
+
+pub fn day2(path: Option<PathBuf>) {
+    let nums = reports(path);
+    let mut trace = Trace::off();
     println!(
         "{} are safe without dampener",
-        nums.iter().filter(|&report| issafe(report)).count()
+        nums.iter()
+            .filter(|&report| issafe(report, &mut trace))
+            .count()
     );
     println!(
         "{} are safe with dampener",
         nums.iter()
-            .filter(|&report| dampener_issafe(report))
+            .filter(|&report| dampener_issafe(report, &mut trace))
             .count()
     );
 }
 
-fn dampener_issafe(report: &Vec<i32>) -> bool {
-    if issafe(report) {
-        println!("no dampener required");
-        return true;
+pub fn explain(path: Option<PathBuf>, line: usize) {
+    let nums = reports(path);
+    let Some(report) = line.checked_sub(1).and_then(|i| nums.get(i)) else {
+        println!("There is no report on line {line}");
+        return;
+    };
+    println!("Report {line}: {:?}", report);
+    let mut trace = Trace::only(line);
+    trace.item(line);
+    let safe = issafe(report, &mut trace);
+    let damped = safe || dampener_issafe(report, &mut trace);
+    for event in trace.events() {
+        println!("  {}", event.detail);
     }
-    (0..report.len()).any(|i| {
-        println!("  checking {:?} without {i}", report);
-        issafe(
-            &report
-                .iter()
-                .enumerate()
-                .filter(|(offset, _level)| *offset != i)
-                .map(|(_offset, level)| *level)
-                .collect(),
-        )
-    })
+    println!(
+        "{} without dampener, {} with dampener",
+        if safe { "safe" } else { "unsafe" },
+        if damped { "safe" } else { "unsafe" }
+    );
 }
 
-fn issafe(report: &Vec<i32>) -> bool {
-    struct Track {
-        last: Option<i32>,
-        last_diff: Option<i32>,
+fn dampener_issafe(report: &[i32], trace: &mut Trace) -> bool {
+    dampen(report, &RULE, 1, trace).is_some()
+}
+
+/// The fewest levels that can be removed to leave a safe report, as their
+/// indices, or `None` if it takes more than `max_removals`.
+///
+/// Whether the rest of a report can be kept safe only depends on the last
+/// level kept, so for each direction `fewest[i]` is the fewest removals
+/// that leave a safe run ending by keeping level `i`. Its predecessor is at
+/// most `max_removals + 1` back, which makes this O(n·k).
+pub fn dampen(
+    report: &[i32],
+    rule: &Rule,
+    max_removals: usize,
+    trace: &mut Trace,
+) -> Option<Vec<usize>> {
+    let n = report.len();
+    if n <= 1 {
+        return Some(Vec::new());
     }
-    let acc = Track {
-        last: None,
-        last_diff: None,
-    };
-    report
-        .iter()
-        .scan(acc, |acc, level| match acc.last {
-            None => {
-                acc.last = Some(*level);
-                Some(level)
+    let mut best: Option<Vec<usize>> = None;
+    for direction in [Direction::Increasing, Direction::Decreasing] {
+        // (removals, index of the previous kept level)
+        let mut fewest: Vec<Option<(usize, Option<usize>)>> = vec![None; n];
+        for i in 0..n {
+            // Keeping i as the first level means removing everything before it.
+            if i <= max_removals {
+                fewest[i] = Some((i, None));
             }
-            Some(last) => {
-                acc.last = Some(*level);
-                let diff: i32 = (last - level).try_into().unwrap();
-                if diff.abs() > 3 {
-                    println!("{last} to {level} not safe because spike ({diff})");
-                    None
-                } else {
-                    match acc.last_diff {
-                        None => {
-                            acc.last_diff = Some(diff);
-                            println!("{last} to {level} safe because no previous ");
-                            Some(level)
-                        }
-                        Some(last_diff) => {
-                            acc.last_diff = Some(diff);
-                            if last_diff < 0 && diff < 0 {
-                                println!("{last} to {level} safe because both diffs negative {last_diff} -> {diff}");
-                                Some(level)
-                            } else if last_diff > 0 && diff > 0 {
-                                println!("{last} to {level} safe because both diffs positive {last_diff} -> {diff}");
-                                Some(level)
-                            } else {
-                                println!("{last} to {level} not safe because wrong direction? {last_diff} -> {diff}");
-                                None
-                            }
-                        }
-                    }
+            for j in i.saturating_sub(max_removals + 1)..i {
+                let Some((removed, _)) = fewest[j] else {
+                    continue;
+                };
+                let removed = removed + (i - j - 1);
+                if removed <= max_removals
+                    && rule.allows(report[j], report[i], direction)
+                    && fewest[i].is_none_or(|(r, _)| removed < r)
+                {
+                    fewest[i] = Some((removed, Some(j)));
                 }
             }
-        })
-        .count()
-        == report.len()
+        }
+        // Keeping i as the last level means removing everything after it.
+        let end = (0..n)
+            .filter_map(|i| fewest[i].map(|(removed, _)| (removed + (n - 1 - i), i)))
+            .filter(|&(removed, _)| removed <= max_removals)
+            .min();
+        let Some((removed, last)) = end else {
+            trace.emit("attempt", || {
+                format!("{direction:?}: needs more than {max_removals} removals")
+            });
+            continue;
+        };
+        trace.emit("attempt", || {
+            format!("{direction:?}: needs {removed} removals")
+        });
+        if best.as_ref().is_some_and(|b| b.len() <= removed) {
+            continue;
+        }
+        let mut kept = vec![false; n];
+        let mut at = Some(last);
+        while let Some(i) = at {
+            kept[i] = true;
+            at = fewest[i].and_then(|(_, previous)| previous);
+        }
+        best = Some((0..n).filter(|&i| !kept[i]).collect());
+    }
+    match &best {
+        Some(removed) if removed.is_empty() => {
+            trace.emit("dampener", || "no dampener required".into())
+        }
+        Some(removed) => trace.emit("dampener", || {
+            let levels: Vec<i32> = removed.iter().map(|&i| report[i]).collect();
+            format!("dampener removed indices {removed:?} (levels {levels:?})")
+        }),
+        None => trace.emit("dampener", || {
+            format!("removing up to {max_removals} levels can't make it safe")
+        }),
+    }
+    best
+}
+
+fn issafe(report: &[i32], trace: &mut Trace) -> bool {
+    let Some(direction) = report.windows(2).next().map(|w| Direction::of(w[0], w[1])) else {
+        return true;
+    };
+    for pair in report.windows(2) {
+        let (last, level) = (pair[0], pair[1]);
+        match RULE.check(last, level, direction) {
+            Ok(()) => trace.emit("step", || {
+                format!("{last} to {level} safe, still {direction:?}")
+            }),
+            Err(Violation::WrongDirection) => {
+                trace.emit("direction", || {
+                    format!("{last} to {level} not safe because wrong direction, report is {direction:?}")
+                });
+                return false;
+            }
+            Err(Violation::TooSmall) => {
+                trace.emit("flat", || {
+                    format!("{last} to {level} not safe because no change")
+                });
+                return false;
+            }
+            Err(Violation::TooBig) => {
+                trace.emit("spike", || {
+                    format!(
+                        "{last} to {level} not safe because spike ({})",
+                        level - last
+                    )
+                });
+                return false;
+            }
+        }
+    }
+    true
 }
 
 fn parser(line: String) -> Vec<i32> {
@@ -133,3 +272,39 @@ fn parser(line: String) -> Vec<i32> {
         .map(|p| p.parse::<i32>().expect("all parts should be ints"))
         .collect()
 }
+
+#[test]
+fn test_dampen() {
+    let mut trace = Trace::off();
+    let example = [
+        vec![7, 6, 4, 2, 1],
+        vec![1, 2, 7, 8, 9],
+        vec![9, 7, 6, 2, 1],
+        vec![1, 3, 2, 4, 5],
+        vec![8, 6, 4, 4, 1],
+        vec![1, 3, 6, 7, 9],
+    ];
+    let safe: Vec<bool> = example.iter().map(|r| issafe(r, &mut trace)).collect();
+    assert_eq!(vec![true, false, false, false, false, true], safe);
+    let removed: Vec<Option<Vec<usize>>> = example
+        .iter()
+        .map(|r| dampen(r, &RULE, 1, &mut trace))
+        .collect();
+    assert_eq!(
+        vec![Some(vec![]), None, None, Some(vec![2]), Some(vec![3]), Some(vec![])],
+        removed
+    );
+    // Two spikes need two removals.
+    let spiky = [1, 2, 9, 3, 4, 20, 5];
+    assert_eq!(None, dampen(&spiky, &RULE, 1, &mut trace));
+    assert_eq!(Some(vec![2, 5]), dampen(&spiky, &RULE, 2, &mut trace));
+    // A wider rule needs none.
+    let climb = [1, 2, 9, 12, 20];
+    assert_eq!(None, dampen(&climb, &RULE, 2, &mut trace));
+    let loose = Rule {
+        min_step: 1,
+        max_step: 8,
+    };
+    assert_eq!(Some(vec![]), dampen(&climb, &loose, 0, &mut trace));
+    assert_eq!(Some(vec![0, 2]), dampen(&[5, 1, 9, 2], &RULE, 3, &mut trace));
+}
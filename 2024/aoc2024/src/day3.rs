@@ -43,77 +43,290 @@ Handle the new instructions; what do you get if you add up all of the results of
 
 */
 
-use crate::util::parse_file;
-use once_cell::sync::Lazy;
-use regex::Regex;
-use std::{
-    borrow::Borrow,
-    fs::File,
-    path::PathBuf,
-    sync::atomic::{AtomicBool, Ordering},
-};
+use crate::trace::Trace;
+use aocutil::parse::{Cursor, ParseError, Position};
+use std::{fs, ops::Range, path::PathBuf};
 
 pub fn day3(path: Option<PathBuf>) {
-    let input = match path.clone() {
-        None => File::open("day3test.txt"),
-        Some(path) => File::open(path),
+    // The examples for the two parts differ, a real input is read once.
+    let (first, second) = match path {
+        None => (read("day3test.txt"), read("day3p2test.txt")),
+        Some(path) => {
+            let memory = read(path);
+            (memory.clone(), memory)
+        }
     };
-    let muls = parse_file(input.unwrap(), parser);
-    let sum: i32 = muls.iter().sum();
-    println!("{:?} sum = {}", muls, sum);
-    let input = match path {
-        None => File::open("day3p2test.txt"),
-        Some(path) => File::open(path),
-    };
-    let muls2 = parse_file(input.unwrap(), parserp2);
-    let sum: i32 = muls2.iter().sum();
-    println!("{:?} sum = {}", muls2, sum);
-}
-
-fn parser(line: String) -> i32 {
-    static RE: Lazy<Regex> = Lazy::new(|| Regex::new(r"mul\(([0-9]{1,3}),([0-9]{1,3})\)").unwrap());
-    RE.captures_iter(&line)
-        .map(|c| {
-            let (_, [sv1, sv2]) = c.extract();
-            let v1: i32 = sv1.parse::<_>().unwrap();
-            let v2: i32 = sv2.parse::<_>().unwrap();
-            let product = v1 * v2;
-            println!("{v1} * {v2} = {}", product);
-            product
-        })
-        .sum()
-}
-
-static ENABLED: AtomicBool = AtomicBool::new(true);
-
-fn parserp2(line: String) -> i32 {
-    static RE: Lazy<Regex> =
-        Lazy::new(|| Regex::new(r#"(don't\(\)|do\(\)|mul\([0-9]{1,3},[0-9]{1,3}\))"#).unwrap());
-    static SUBRE: Lazy<Regex> =
-        Lazy::new(|| Regex::new(r"mul\(([0-9]{1,3}),([0-9]{1,3})\)").unwrap());
-    RE.find_iter(&line)
-        .filter_map(|m| {
-            let cmd = m.as_str();
-            if cmd == "do()" {
-                ENABLED.store(true, Ordering::Relaxed);
-                None
-            } else if cmd == "don't()" {
-                ENABLED.store(false, Ordering::Relaxed);
-                None
-            } else {
-                if ENABLED.load(Ordering::Relaxed) {
-                    let subc = SUBRE.captures(cmd).unwrap();
-                    let (_, [sv1, sv2]) = subc.extract();
-                    let v1: i32 = sv1.parse::<_>().unwrap();
-                    let v2: i32 = sv2.parse::<_>().unwrap();
-                    let product = v1 * v2;
-                    println!("{v1} * {v2} = {}", product);
-                    Some(product)
-                } else {
-                    println!("skipping {cmd}");
-                    None
+    let mut trace = Trace::off();
+    let tokens = Tokenizer::mul_only().tokens(&first);
+    let sum = Machine::unconditional().run(&tokens, &mut trace);
+    println!("{} instructions sum = {}", tokens.len(), sum);
+    let tokens = Tokenizer::standard().tokens(&second);
+    let sum = Machine::new().run(&tokens, &mut trace);
+    println!("{} instructions sum = {}", tokens.len(), sum);
+}
+
+/// Replays part two, printing every instruction found on `line` with the
+/// span it was read from. Earlier lines still run so that a `don't()` near
+/// the end of one line carries over into the next.
+pub fn explain(path: Option<PathBuf>, line: usize) {
+    let memory = read(path.unwrap_or_else(|| "day3p2test.txt".into()));
+    if line == 0 || line > memory.lines().count() {
+        println!("There is no line {line}");
+        return;
+    }
+    let mut trace = Trace::only(line);
+    let mut machine = Machine::new();
+    let mut sum = 0;
+    let mut started = false;
+    for token in Tokenizer::standard().tokens(&memory) {
+        trace.item(token.position.line);
+        if token.position.line == line && !started {
+            started = true;
+            let enabled = machine.enabled;
+            trace.emit("start", || {
+                format!(
+                    "mul is {} at start of line",
+                    if enabled { "enabled" } else { "disabled" }
+                )
+            });
+        }
+        let added = machine.step(&token, &mut trace);
+        if token.position.line == line {
+            sum += added;
+        }
+    }
+    for event in trace.events() {
+        println!("  {}", event.detail);
+    }
+    println!("Line {line} adds {sum}");
+}
+
+fn read<P: Into<PathBuf>>(path: P) -> String {
+    fs::read_to_string(path.into()).unwrap()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Instruction {
+    Mul(i64, i64),
+    Do,
+    Dont,
+}
+
+/// An instruction found in memory. `span` is the byte range it was read
+/// from and `position` where that range starts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub instruction: Instruction,
+    pub span: Range<usize>,
+    pub position: Position,
+}
+
+/// Reads one instruction at the cursor, or fails without caring where the
+/// cursor is left.
+pub type Recognizer = fn(&mut Cursor) -> Result<Instruction, ParseError>;
+
+/// Scans memory for the instructions it has recognizers for. At each byte
+/// the recognizers are tried in order and the first to match wins; when
+/// none do the byte is corrupted and skipped.
+#[derive(Debug, Clone, Default)]
+pub struct Tokenizer {
+    recognizers: Vec<Recognizer>,
+}
+
+impl Tokenizer {
+    pub fn new() -> Tokenizer {
+        Tokenizer::default()
+    }
+
+    /// Part one only knows about `mul`.
+    pub fn mul_only() -> Tokenizer {
+        Tokenizer::new().with(mul)
+    }
+
+    pub fn standard() -> Tokenizer {
+        Tokenizer::mul_only().with(enable).with(disable)
+    }
+
+    pub fn with(mut self, recognizer: Recognizer) -> Tokenizer {
+        self.recognizers.push(recognizer);
+        self
+    }
+
+    /// Every instruction in `memory`, which is scanned as one stream so
+    /// line breaks are just more corruption.
+    pub fn tokens(&self, memory: &str) -> Vec<Token> {
+        let mut c = Cursor::new(memory);
+        let mut tokens = Vec::new();
+        // Where the last token started, carried forward from one token to
+        // the next rather than counted again from the start each time.
+        let mut seen = 0;
+        let mut position = Position { line: 1, column: 1 };
+        while !c.is_empty() {
+            let start = c.offset();
+            let found = self
+                .recognizers
+                .iter()
+                .find_map(|recognize| c.attempt(recognize).ok());
+            match found {
+                Some(instruction) => {
+                    position = advance(position, &memory[seen..start]);
+                    seen = start;
+                    tokens.push(Token {
+                        instruction,
+                        span: start..c.offset(),
+                        position,
+                    });
                 }
+                None => {
+                    c.bump();
+                }
+            }
+        }
+        tokens
+    }
+}
+
+/// Where reading `text` from `position` ends up.
+fn advance(mut position: Position, text: &str) -> Position {
+    for ch in text.chars() {
+        if ch == '\n' {
+            position.line += 1;
+            position.column = 1;
+        } else {
+            position.column += 1;
+        }
+    }
+    position
+}
+
+fn mul(c: &mut Cursor) -> Result<Instruction, ParseError> {
+    c.tag("mul(")?;
+    let v1 = c.digits(3)?;
+    c.tag(",")?;
+    let v2 = c.digits(3)?;
+    c.tag(")")?;
+    Ok(Instruction::Mul(v1, v2))
+}
+
+fn enable(c: &mut Cursor) -> Result<Instruction, ParseError> {
+    c.tag("do()")?;
+    Ok(Instruction::Do)
+}
+
+fn disable(c: &mut Cursor) -> Result<Instruction, ParseError> {
+    c.tag("don't()")?;
+    Ok(Instruction::Dont)
+}
+
+/// Runs instructions, keeping the `do()`/`don't()` state between them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Machine {
+    enabled: bool,
+    conditionals: bool,
+    sum: i64,
+}
+
+impl Machine {
+    pub fn new() -> Machine {
+        Machine {
+            enabled: true,
+            conditionals: true,
+            sum: 0,
+        }
+    }
+
+    /// A machine that ignores `do()` and `don't()`.
+    pub fn unconditional() -> Machine {
+        Machine {
+            conditionals: false,
+            ..Machine::new()
+        }
+    }
+
+    pub fn sum(&self) -> i64 {
+        self.sum
+    }
+
+    /// Carries out one instruction, returning what it added to the sum.
+    pub fn step(&mut self, token: &Token, trace: &mut Trace) -> i64 {
+        let at = token.position;
+        let span = &token.span;
+        match token.instruction {
+            Instruction::Do if self.conditionals => {
+                trace.emit("do", || format!("{at} {span:?}: do() enables mul"));
+                self.enabled = true;
             }
-        })
-        .sum()
+            Instruction::Dont if self.conditionals => {
+                trace.emit("don't", || format!("{at} {span:?}: don't() disables mul"));
+                self.enabled = false;
+            }
+            Instruction::Do | Instruction::Dont => {}
+            Instruction::Mul(v1, v2) if self.enabled => {
+                let product = v1 * v2;
+                trace.emit("mul", || format!("{at} {span:?}: {v1} * {v2} = {product}"));
+                self.sum += product;
+                return product;
+            }
+            Instruction::Mul(v1, v2) => {
+                trace.emit("skip", || format!("{at} {span:?}: skipping mul({v1},{v2})"));
+            }
+        }
+        0
+    }
+
+    /// Runs every token and returns the sum so far.
+    pub fn run(&mut self, tokens: &[Token], trace: &mut Trace) -> i64 {
+        for token in tokens {
+            self.step(token, trace);
+        }
+        self.sum
+    }
+}
+
+impl Default for Machine {
+    fn default() -> Machine {
+        Machine::new()
+    }
+}
+
+#[test]
+fn test_tokens_and_spans() {
+    let memory = "xmul(2,4)&mul[3,7]!^don't()_mul(5,5)+mul(32,64](mul(11,8)undo()?mul(8,5))";
+    let tokens = Tokenizer::standard().tokens(memory);
+    let found: Vec<_> = tokens.iter().map(|t| &memory[t.span.clone()]).collect();
+    assert_eq!(
+        vec![
+            "mul(2,4)",
+            "don't()",
+            "mul(5,5)",
+            "mul(11,8)",
+            "do()",
+            "mul(8,5)"
+        ],
+        found
+    );
+    assert_eq!(Instruction::Mul(11, 8), tokens[3].instruction);
+    assert_eq!(48..57, tokens[3].span);
+    assert_eq!(49, tokens[3].position.column);
+
+    let mut trace = Trace::off();
+    assert_eq!(161, Machine::unconditional().run(&tokens, &mut trace));
+    assert_eq!(48, Machine::new().run(&tokens, &mut trace));
+    let muls = Tokenizer::mul_only().tokens(memory);
+    assert_eq!(4, muls.len());
+}
+
+#[test]
+fn test_state_carries_across_lines() {
+    let memory = "mul(1,2)don't()\nmul(3,4)\ndo()mul(5,6)don't()";
+    let tokens = Tokenizer::standard().tokens(memory);
+    assert_eq!(Position { line: 2, column: 1 }, tokens[2].position);
+    assert_eq!(Position { line: 3, column: 5 }, tokens[4].position);
+    let mut machine = Machine::new();
+    assert_eq!(32, machine.run(&tokens, &mut Trace::off()));
+    // A second run picks up where the last don't() left off, adding
+    // nothing, while a fresh machine starts enabled again.
+    let more = Tokenizer::standard().tokens("mul(7,8)");
+    assert_eq!(32, machine.run(&more, &mut Trace::off()));
+    assert_eq!(56, Machine::new().run(&more, &mut Trace::off()));
 }
@@ -1,8 +1,11 @@
 pub mod day1;
 pub mod day2;
 pub mod day3;
+pub mod trace;
 pub mod util;
 
+use std::path::PathBuf;
+
 use clap::{Parser, Subcommand};
 
 #[derive(Parser)]
@@ -21,6 +24,17 @@ enum Commands {
     Day1 {},
     Day2 {},
     Day3 {},
+    /// Show the reasoning behind one line of a day's answer
+    Explain {
+        #[arg(long)]
+        day: u8,
+        /// 1-based line of the input
+        #[arg(long)]
+        line: usize,
+        /// Input file, defaults to the day's example
+        #[arg(long)]
+        input: Option<PathBuf>,
+    },
 }
 
 fn main() {
@@ -46,6 +60,11 @@ fn main() {
             day3::day3(None);
             day3::day3(Some("2024d3p1.txt".into()));
         }
+        Some(Commands::Explain { day, line, input }) => match day {
+            2 => day2::explain(input.clone(), *line),
+            3 => day3::explain(input.clone(), *line),
+            day => println!("Day {day} can't explain its answers yet"),
+        },
         None => {}
     }
 
@@ -0,0 +1,121 @@
+/*
+Structured reasoning trails for `explain`.
+
+Solutions take a `&mut Trace` and report each decision they make as an
+`Event` tagged with the input item (line) it belongs to. A normal run passes
+`Trace::off()`, which records nothing and never even formats the details; an
+`explain` run keeps only the events for the item being asked about.
+*/
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// 1-based input item (usually the line) this decision was about.
+    pub item: usize,
+    /// Short machine-friendly tag, e.g. "spike" or "skip".
+    pub kind: &'static str,
+    pub detail: String,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {}: {}", self.item, self.kind, self.detail)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Filter {
+    Off,
+    All,
+    Only(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct Trace {
+    filter: Filter,
+    item: usize,
+    events: Vec<Event>,
+}
+
+impl Trace {
+    pub fn off() -> Trace {
+        Trace::new(Filter::Off)
+    }
+
+    pub fn all() -> Trace {
+        Trace::new(Filter::All)
+    }
+
+    /// Keeps events for one item and drops the rest.
+    pub fn only(item: usize) -> Trace {
+        Trace::new(Filter::Only(item))
+    }
+
+    fn new(filter: Filter) -> Trace {
+        Trace {
+            filter,
+            item: 0,
+            events: Vec::new(),
+        }
+    }
+
+    /// Later events belong to `item`.
+    pub fn item(&mut self, item: usize) {
+        self.item = item;
+    }
+
+    /// Whether events for the current item are being kept.
+    pub fn enabled(&self) -> bool {
+        match self.filter {
+            Filter::Off => false,
+            Filter::All => true,
+            Filter::Only(item) => item == self.item,
+        }
+    }
+
+    /// Records an event; `detail` is only called if it will be kept.
+    pub fn emit<F>(&mut self, kind: &'static str, detail: F)
+    where
+        F: FnOnce() -> String,
+    {
+        if self.enabled() {
+            self.events.push(Event {
+                item: self.item,
+                kind,
+                detail: detail(),
+            });
+        }
+    }
+
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+}
+
+#[test]
+fn test_filters() {
+    let mut called = false;
+    let mut off = Trace::off();
+    off.emit("never", || {
+        called = true;
+        String::new()
+    });
+    assert!(!called);
+    assert!(off.events().is_empty());
+
+    let mut only = Trace::only(2);
+    for item in 1..=3 {
+        only.item(item);
+        only.emit("seen", || format!("item {item}"));
+    }
+    assert_eq!(
+        vec![Event {
+            item: 2,
+            kind: "seen",
+            detail: "item 2".into()
+        }],
+        only.events()
+    );
+    assert_eq!("[2] seen: item 2", only.events()[0].to_string());
+}
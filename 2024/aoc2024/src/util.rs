@@ -3,6 +3,8 @@ use std::{
     io::{self, BufRead},
 };
 
+use aocutil::parse::{self, Cursor, ParseError};
+
 pub(crate) fn parse_file<F, T>(input: File, parser: F) -> Vec<T>
 where
     F: FnMut(String) -> T,
@@ -14,3 +16,11 @@ where
         .map(parser)
         .collect()
 }
+
+pub(crate) fn parse_input<F, T>(input: File, parser: F) -> Result<Vec<T>, ParseError>
+where
+    F: for<'a> FnMut(&mut Cursor<'a>) -> Result<T, ParseError>,
+{
+    let text = io::read_to_string(input).unwrap();
+    parse::lines(&text, parser)
+}
@@ -0,0 +1,171 @@
+/*
+Lining crabs up: the position that costs the least to move everyone to.
+
+Moving a crab `d` steps costs `f(d)`, and the total for a position is that
+summed over every crab. For a linear cost the best position is the median.
+For the triangular cost `d(d+1)/2` it is within half a step of the mean, so
+only the few integers around it need checking. Any other cost that grows
+convexly with distance makes the total convex in the position, and the
+minimum is found by ternary search between the outermost crabs.
+*/
+
+#[derive(Debug, Copy, Clone)]
+pub enum Cost {
+    /// `d`: one unit of fuel per step.
+    Linear,
+    /// `1 + 2 + ... + d`: each step costs one more than the last.
+    Triangular,
+    /// Any cost with `f(0) = 0` that never grows by less than it did the
+    /// step before.
+    Convex(fn(u64) -> u64),
+}
+
+impl Cost {
+    /// What moving one crab `distance` steps costs.
+    pub fn of(self, distance: u64) -> u64 {
+        match self {
+            Cost::Linear => distance,
+            Cost::Triangular => distance * (distance + 1) / 2,
+            Cost::Convex(f) => f(distance),
+        }
+    }
+
+    /// What moving every crab to `position` costs.
+    pub fn total(self, crabs: &[i64], position: i64) -> u64 {
+        crabs
+            .iter()
+            .map(|&crab| self.of(crab.abs_diff(position)))
+            .sum()
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Alignment {
+    pub position: i64,
+    pub cost: u64,
+}
+
+/// The cheapest position to line `crabs` up on, or `None` if there are no
+/// crabs. When several positions cost the same the leftmost is returned.
+pub fn align(crabs: &[i64], cost: Cost) -> Option<Alignment> {
+    let lo = *crabs.iter().min()?;
+    let hi = *crabs.iter().max()?;
+    let position = match cost {
+        Cost::Linear => median(crabs),
+        Cost::Triangular => {
+            let n = crabs.len() as i64;
+            let sum: i64 = crabs.iter().sum();
+            let mean = sum.div_euclid(n);
+            // The real minimum is within 1/2 of the mean, so one either
+            // side of its floor covers every integer that could win.
+            let candidates = (mean - 1).max(lo)..=(mean + 1).min(hi);
+            cheapest(candidates, |p| cost.total(crabs, p))
+        }
+        Cost::Convex(_) => ternary_search(lo, hi, |p| cost.total(crabs, p)),
+    };
+    Some(Alignment {
+        position,
+        cost: cost.total(crabs, position),
+    })
+}
+
+/// The lower median, which is the leftmost position any linear cost is
+/// lowest at.
+fn median(crabs: &[i64]) -> i64 {
+    let mut sorted = crabs.to_vec();
+    sorted.sort_unstable();
+    sorted[(sorted.len() - 1) / 2]
+}
+
+fn cheapest<I, F>(positions: I, total: F) -> i64
+where
+    I: IntoIterator<Item = i64>,
+    F: Fn(i64) -> u64,
+{
+    positions
+        .into_iter()
+        .min_by_key(|&p| (total(p), p))
+        .expect("at least one candidate")
+}
+
+/// The leftmost minimum of a convex `total` on `lo..=hi`. Comparing
+/// neighbours rather than thirds keeps flat stretches from fooling it.
+fn ternary_search<F>(mut lo: i64, mut hi: i64, total: F) -> i64
+where
+    F: Fn(i64) -> u64,
+{
+    while lo < hi {
+        let mid = lo + (hi - lo) / 2;
+        if total(mid) <= total(mid + 1) {
+            hi = mid;
+        } else {
+            lo = mid + 1;
+        }
+    }
+    lo
+}
+
+#[cfg(test)]
+const EXAMPLE: [i64; 10] = [16, 1, 2, 0, 4, 2, 7, 1, 2, 14];
+
+#[cfg(test)]
+fn brute_force(crabs: &[i64], cost: Cost) -> Alignment {
+    let lo = *crabs.iter().min().unwrap();
+    let hi = *crabs.iter().max().unwrap();
+    let position = cheapest(lo..=hi, |p| cost.total(crabs, p));
+    Alignment {
+        position,
+        cost: cost.total(crabs, position),
+    }
+}
+
+#[test]
+fn test_crab_submarines() {
+    let linear = align(&EXAMPLE, Cost::Linear).unwrap();
+    assert_eq!(
+        Alignment {
+            position: 2,
+            cost: 37
+        },
+        linear
+    );
+    assert_eq!(41, Cost::Linear.total(&EXAMPLE, 1));
+    let triangular = align(&EXAMPLE, Cost::Triangular).unwrap();
+    assert_eq!(
+        Alignment {
+            position: 5,
+            cost: 168
+        },
+        triangular
+    );
+    assert_eq!(206, Cost::Triangular.total(&EXAMPLE, 2));
+    assert_eq!(None, align(&[], Cost::Linear));
+}
+
+#[test]
+fn test_convex_costs() {
+    // A linear cost handed in as a function lands where the median does.
+    let linear = Cost::Convex(|d| d);
+    assert_eq!(align(&EXAMPLE, Cost::Linear), align(&EXAMPLE, linear));
+    let squared = Cost::Convex(|d| d * d);
+    assert_eq!(
+        brute_force(&EXAMPLE, squared),
+        align(&EXAMPLE, squared).unwrap()
+    );
+    // Free for the first three steps: a wide flat bottom.
+    let lazy = Cost::Convex(|d| d.saturating_sub(3));
+    assert_eq!(brute_force(&EXAMPLE, lazy), align(&EXAMPLE, lazy).unwrap());
+}
+
+#[cfg(test)]
+use proptest::prelude::*;
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn prop_matches_brute_force(crabs in proptest::collection::vec(-50i64..150, 1..30)) {
+        for cost in [Cost::Linear, Cost::Triangular, Cost::Convex(|d| d * d * d)] {
+            prop_assert_eq!(brute_force(&crabs, cost), align(&crabs, cost).unwrap());
+        }
+    }
+}
@@ -0,0 +1,363 @@
+/*
+Bingo against a giant squid.
+
+The input is a line of called numbers followed by square boards separated
+by blank lines. `Bingo::play` calls every number in turn, marks it on each
+board still in the game, and returns the boards in the order they win along
+with the number that did it, the score (the unmarked numbers' sum times that
+number) and which line was completed. Boards keep a count of marks per row,
+column and diagonal, so spotting a win is O(1) per mark.
+*/
+
+use std::{error, fmt};
+
+use crate::parse::{self, Cursor, ErrorKind, ParseError, Position};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BingoError {
+    Parse(ParseError),
+    /// There was a line of numbers to call but no boards to mark.
+    NoBoards,
+    /// The board starting on `line` has the wrong number of rows.
+    WrongSize {
+        line: usize,
+        rows: usize,
+        size: usize,
+    },
+}
+
+impl fmt::Display for BingoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BingoError::Parse(e) => write!(f, "{}", e),
+            BingoError::NoBoards => write!(f, "no boards to play on"),
+            BingoError::WrongSize { line, rows, size } => write!(
+                f,
+                "board on line {} has {} rows, expected {}",
+                line, rows, size
+            ),
+        }
+    }
+}
+
+impl error::Error for BingoError {}
+
+impl From<ParseError> for BingoError {
+    fn from(e: ParseError) -> BingoError {
+        BingoError::Parse(e)
+    }
+}
+
+/// A completed line on a board.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Line {
+    Row(usize),
+    Column(usize),
+    /// Top left to bottom right.
+    Diagonal,
+    /// Top right to bottom left.
+    AntiDiagonal,
+}
+
+/// A board winning. `draw` is the index of `number` in the called numbers.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Win {
+    pub board: usize,
+    pub draw: usize,
+    pub number: u32,
+    pub score: u64,
+    pub line: Line,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Board {
+    size: usize,
+    cells: Vec<u32>,
+    marked: Vec<bool>,
+    row_marks: Vec<usize>,
+    column_marks: Vec<usize>,
+    /// Marks on the diagonal and the anti-diagonal.
+    diagonal_marks: [usize; 2],
+}
+
+impl Board {
+    /// A `size`×`size` board with `cells` in reading order.
+    pub fn new(size: usize, cells: Vec<u32>) -> Board {
+        assert_eq!(size * size, cells.len(), "board must be square");
+        Board {
+            size,
+            marked: vec![false; cells.len()],
+            cells,
+            row_marks: vec![0; size],
+            column_marks: vec![0; size],
+            diagonal_marks: [0; 2],
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> u32 {
+        self.cells[row * self.size + column]
+    }
+
+    pub fn is_marked(&self, row: usize, column: usize) -> bool {
+        self.marked[row * self.size + column]
+    }
+
+    /// Marks every cell holding `number` and returns the first line that
+    /// marking completed, if any. Diagonals only count if `diagonals`.
+    pub fn mark(&mut self, number: u32, diagonals: bool) -> Option<Line> {
+        let mut completed = None;
+        for i in 0..self.cells.len() {
+            if self.cells[i] != number || self.marked[i] {
+                continue;
+            }
+            self.marked[i] = true;
+            let (row, column) = (i / self.size, i % self.size);
+            let mut lines = vec![(&mut self.row_marks[row], Line::Row(row))];
+            lines.push((&mut self.column_marks[column], Line::Column(column)));
+            let [diagonal, anti] = &mut self.diagonal_marks;
+            if diagonals && row == column {
+                lines.push((diagonal, Line::Diagonal));
+            }
+            if diagonals && row + column == self.size - 1 {
+                lines.push((anti, Line::AntiDiagonal));
+            }
+            for (marks, line) in lines {
+                *marks += 1;
+                if *marks == self.size && completed.is_none() {
+                    completed = Some(line);
+                }
+            }
+        }
+        completed
+    }
+
+    pub fn unmarked_sum(&self) -> u64 {
+        self.cells
+            .iter()
+            .zip(&self.marked)
+            .filter(|(_, &marked)| !marked)
+            .map(|(&n, _)| n as u64)
+            .sum()
+    }
+}
+
+/// Numbers right-aligned as in the puzzle, with marked ones replaced by `X`.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.cells.iter().map(|n| n.to_string().len()).max();
+        let width = width.unwrap_or(1);
+        for row in 0..self.size {
+            for column in 0..self.size {
+                if column > 0 {
+                    write!(f, " ")?;
+                }
+                if self.is_marked(row, column) {
+                    write!(f, "{:>width$}", "X")?;
+                } else {
+                    write!(f, "{:>width$}", self.get(row, column))?;
+                }
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bingo {
+    draw: Vec<u32>,
+    boards: Vec<Board>,
+    diagonals: bool,
+}
+
+impl Bingo {
+    /// Called numbers and `size`×`size` boards. Diagonals don't count
+    /// unless turned on with `with_diagonals`.
+    pub fn parse(input: &str, size: usize) -> Result<Bingo, BingoError> {
+        let blocks = parse::blocks(input);
+        let Some((first, rest)) = blocks.split_first() else {
+            return Err(BingoError::NoBoards);
+        };
+        let draw = first.lines(|c| c.separated(",", |c| c.int()))?;
+        let draw = draw.into_iter().flatten().collect();
+        if rest.is_empty() {
+            return Err(BingoError::NoBoards);
+        }
+        let mut boards = Vec::with_capacity(rest.len());
+        for block in rest {
+            let rows = block.lines(|c| board_row(c, size))?;
+            if rows.len() != size {
+                return Err(BingoError::WrongSize {
+                    line: block.line,
+                    rows: rows.len(),
+                    size,
+                });
+            }
+            boards.push(Board::new(size, rows.concat()));
+        }
+        Ok(Bingo {
+            draw,
+            boards,
+            diagonals: false,
+        })
+    }
+
+    pub fn with_diagonals(mut self, diagonals: bool) -> Bingo {
+        self.diagonals = diagonals;
+        self
+    }
+
+    pub fn draw(&self) -> &[u32] {
+        &self.draw
+    }
+
+    pub fn boards(&self) -> &[Board] {
+        &self.boards
+    }
+
+    /// Calls every number and returns the winners in the order they won.
+    /// Boards that win on the same number are in board order. A board
+    /// stops being marked once it has won, and boards that never win are
+    /// left out.
+    pub fn play(&self) -> Vec<Win> {
+        self.play_out().0
+    }
+
+    /// `play`, along with every board as it was left at the end: winners
+    /// as they were when they won, the rest with everything called marked.
+    pub fn play_out(&self) -> (Vec<Win>, Vec<Board>) {
+        let mut boards = self.boards.clone();
+        let mut won = vec![false; boards.len()];
+        let mut wins = Vec::new();
+        for (draw, &number) in self.draw.iter().enumerate() {
+            for (i, board) in boards.iter_mut().enumerate() {
+                if won[i] {
+                    continue;
+                }
+                if let Some(line) = board.mark(number, self.diagonals) {
+                    won[i] = true;
+                    wins.push(Win {
+                        board: i,
+                        draw,
+                        number,
+                        score: board.unmarked_sum() * number as u64,
+                        line,
+                    });
+                }
+            }
+            if wins.len() == boards.len() {
+                break;
+            }
+        }
+        (wins, boards)
+    }
+}
+
+fn board_row(c: &mut Cursor, size: usize) -> parse::Result<Vec<u32>> {
+    let start = c.position();
+    let mut row = Vec::with_capacity(size);
+    c.skip_whitespace();
+    while !c.is_empty() {
+        row.push(c.int()?);
+        c.skip_whitespace();
+    }
+    if row.len() != size {
+        return Err(ParseError {
+            position: Position { column: 1, ..start },
+            kind: ErrorKind::RaggedGrid {
+                expected: size,
+                found: row.len(),
+            },
+        });
+    }
+    Ok(row)
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+
+22 13 17 11  0
+ 8  2 23  4 24
+21  9 14 16  7
+ 6 10  3 18  5
+ 1 12 20 15 19
+
+ 3 15  0  2 22
+ 9 18 13 17  5
+19  8  7 25 23
+20 11 10 24  4
+14 21 16 12  6
+
+14 21 17 24  4
+10 16 15  9 19
+18  8 23 26 20
+22 11 13  6  5
+ 2  0 12  3  7
+";
+
+#[test]
+fn test_giant_squid() {
+    let bingo = Bingo::parse(EXAMPLE, 5).unwrap();
+    assert_eq!(27, bingo.draw().len());
+    assert_eq!(3, bingo.boards().len());
+    let wins = bingo.play();
+    let first = Win {
+        board: 2,
+        draw: 11,
+        number: 24,
+        score: 4512,
+        line: Line::Row(0),
+    };
+    assert_eq!(first, wins[0]);
+    assert_eq!(
+        vec![2, 0, 1],
+        wins.iter().map(|w| w.board).collect::<Vec<_>>()
+    );
+    let last = wins.last().unwrap();
+    assert_eq!(
+        (13, 1924, Line::Column(2)),
+        (last.number, last.score, last.line)
+    );
+}
+
+#[test]
+fn test_diagonals() {
+    let input = "1,5,9,2\n\n1 2 3\n4 5 6\n7 8 9\n\n3 2 1\n4 5 6\n9 8 7\n";
+    let bingo = Bingo::parse(input, 3).unwrap();
+    assert_eq!(Vec::<Win>::new(), bingo.play());
+    let bingo = bingo.with_diagonals(true);
+    let wins = bingo.play();
+    assert_eq!(2, wins.len());
+    assert_eq!((9, Line::Diagonal), (wins[0].number, wins[0].line));
+    assert_eq!((2 + 3 + 4 + 6 + 7 + 8) * 9, wins[0].score);
+    assert_eq!(Line::AntiDiagonal, wins[1].line);
+    let (_, boards) = bingo.play_out();
+    assert_eq!("X 2 3\n4 X 6\n7 8 X\n", boards[0].to_string());
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(Err(BingoError::NoBoards), Bingo::parse("1,2,3\n", 5));
+    assert_eq!(
+        "4:1: grid row is 2 wide, expected 3",
+        Bingo::parse("1,2\n\n1 2 3\n4 5\n", 3)
+            .unwrap_err()
+            .to_string()
+    );
+    assert_eq!(
+        Err(BingoError::WrongSize {
+            line: 3,
+            rows: 1,
+            size: 2
+        }),
+        Bingo::parse("1\n\n1 2\n", 2)
+    );
+    assert_eq!(
+        "1:3: expected an integer",
+        Bingo::parse("1,x\n\n1\n", 1).unwrap_err().to_string()
+    );
+}
@@ -0,0 +1,280 @@
+/*
+Columns of fixed-width binary numbers, as in a submarine diagnostic report.
+
+`BitColumns` holds the numbers along with their width (anything from 1 to
+128 bits, taken from the input), and answers questions about each column:
+how many ones and zeros it has, the most and least common bit of every
+column packed into a mask, and which single number survives repeatedly
+filtering on a bit criterion from the leftmost column rightwards.
+*/
+
+use std::{error, fmt};
+
+use crate::parse::{self, Cursor, ErrorKind, ParseError, Position};
+
+/// The widest number a `u128` can hold.
+pub const MAX_WIDTH: usize = 128;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitsError {
+    Parse(ParseError),
+    /// The numbers are wider than `MAX_WIDTH`.
+    TooWide(usize),
+    /// There were no numbers to work with.
+    Empty,
+    /// Every column was used up with this many numbers still left, which
+    /// only happens when the input has duplicates.
+    Ambiguous(usize),
+}
+
+impl fmt::Display for BitsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BitsError::Parse(e) => write!(f, "{}", e),
+            BitsError::TooWide(width) => {
+                write!(
+                    f,
+                    "numbers are {} bits wide, at most {} supported",
+                    width, MAX_WIDTH
+                )
+            }
+            BitsError::Empty => write!(f, "no numbers given"),
+            BitsError::Ambiguous(left) => {
+                write!(f, "ran out of bits with {} numbers still matching", left)
+            }
+        }
+    }
+}
+
+impl error::Error for BitsError {}
+
+impl From<ParseError> for BitsError {
+    fn from(e: ParseError) -> BitsError {
+        BitsError::Parse(e)
+    }
+}
+
+/// How many ones and zeros one column has.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ColumnCount {
+    pub ones: usize,
+    pub zeros: usize,
+}
+
+/// Oxygen generator criterion: keep the most common bit, ones on a tie.
+pub fn most_common(ones: usize, zeros: usize) -> bool {
+    ones >= zeros
+}
+
+/// CO2 scrubber criterion: keep the least common bit, zeros on a tie.
+pub fn least_common(ones: usize, zeros: usize) -> bool {
+    ones < zeros
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitColumns {
+    width: usize,
+    values: Vec<u128>,
+}
+
+impl BitColumns {
+    /// Numbers that are `width` bits wide. Bits above the width are
+    /// dropped.
+    pub fn new(values: Vec<u128>, width: usize) -> Result<BitColumns, BitsError> {
+        if width > MAX_WIDTH {
+            return Err(BitsError::TooWide(width));
+        }
+        let mut columns = BitColumns { width, values };
+        let mask = columns.mask();
+        for value in &mut columns.values {
+            *value &= mask;
+        }
+        Ok(columns)
+    }
+
+    /// One binary number per line, all the same width. Blank lines at the
+    /// end are ignored.
+    pub fn parse(input: &str) -> Result<BitColumns, BitsError> {
+        let rows = parse::lines(input.trim_end(), |c| {
+            let position = c.position();
+            let digits = binary_digits(c)?;
+            Ok((position, digits))
+        })?;
+        let Some(&(_, first)) = rows.first() else {
+            return Err(BitsError::Empty);
+        };
+        let width = first.len();
+        if width > MAX_WIDTH {
+            return Err(BitsError::TooWide(width));
+        }
+        let mut values = Vec::with_capacity(rows.len());
+        for (position, digits) in rows {
+            if digits.len() != width {
+                return Err(ragged(position, width, digits.len()).into());
+            }
+            values.push(u128::from_str_radix(digits, 2).expect("only binary digits"));
+        }
+        Ok(BitColumns { width, values })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn values(&self) -> &[u128] {
+        &self.values
+    }
+
+    /// All `width` bits set.
+    pub fn mask(&self) -> u128 {
+        match self.width {
+            0 => 0,
+            width => u128::MAX >> (MAX_WIDTH - width),
+        }
+    }
+
+    /// Counts for every column, leftmost (most significant) first.
+    pub fn counts(&self) -> Vec<ColumnCount> {
+        (0..self.width)
+            .rev()
+            .map(|bit| count(&self.values, bit))
+            .collect()
+    }
+
+    /// The most common bit of each column. A tied column gives 0.
+    pub fn gamma(&self) -> u128 {
+        self.pack(|c| c.ones > c.zeros)
+    }
+
+    /// The least common bit of each column: the complement of `gamma`.
+    pub fn epsilon(&self) -> u128 {
+        !self.gamma() & self.mask()
+    }
+
+    fn pack<F: Fn(ColumnCount) -> bool>(&self, bit: F) -> u128 {
+        self.counts()
+            .into_iter()
+            .fold(0, |acc, c| acc << 1 | bit(c) as u128)
+    }
+
+    /// Filters column by column from the left, keeping the numbers with a
+    /// one there if `keep_ones(ones, zeros)` says so and the ones with a
+    /// zero otherwise, until one number is left. A column where every
+    /// number agrees keeps them all, whatever the criterion says.
+    pub fn filter<F>(&self, keep_ones: F) -> Result<u128, BitsError>
+    where
+        F: Fn(usize, usize) -> bool,
+    {
+        let mut left = self.values.clone();
+        for bit in (0..self.width).rev() {
+            if left.len() <= 1 {
+                break;
+            }
+            let c = count(&left, bit);
+            if c.ones == 0 || c.zeros == 0 {
+                continue;
+            }
+            let want = keep_ones(c.ones, c.zeros);
+            left.retain(|v| (v >> bit & 1 == 1) == want);
+        }
+        match left.len() {
+            0 => Err(BitsError::Empty),
+            1 => Ok(left[0]),
+            n => Err(BitsError::Ambiguous(n)),
+        }
+    }
+}
+
+fn count(values: &[u128], bit: usize) -> ColumnCount {
+    let ones = values.iter().filter(|v| *v >> bit & 1 == 1).count();
+    ColumnCount {
+        ones,
+        zeros: values.len() - ones,
+    }
+}
+
+fn binary_digits<'a>(c: &mut Cursor<'a>) -> parse::Result<&'a str> {
+    let rest = c.rest();
+    let len = rest
+        .bytes()
+        .take_while(|b| matches!(b, b'0' | b'1'))
+        .count();
+    if len == 0 {
+        return Err(c.error(ErrorKind::Expected("binary digit".into())));
+    }
+    for _ in 0..len {
+        c.bump();
+    }
+    Ok(&rest[..len])
+}
+
+fn ragged(position: Position, expected: usize, found: usize) -> ParseError {
+    ParseError {
+        position,
+        kind: ErrorKind::RaggedGrid { expected, found },
+    }
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "00100
+11110
+10110
+10111
+10101
+01111
+00111
+11100
+10000
+11001
+00010
+01010
+";
+
+#[test]
+fn test_power_consumption() {
+    let report = BitColumns::parse(EXAMPLE).unwrap();
+    assert_eq!(5, report.width());
+    assert_eq!(ColumnCount { ones: 7, zeros: 5 }, report.counts()[0]);
+    assert_eq!(22, report.gamma());
+    assert_eq!(9, report.epsilon());
+}
+
+#[test]
+fn test_life_support() {
+    let report = BitColumns::parse(EXAMPLE).unwrap();
+    assert_eq!(Ok(23), report.filter(most_common));
+    assert_eq!(Ok(10), report.filter(least_common));
+}
+
+#[test]
+fn test_full_width() {
+    let wide = format!("1{}\n0{}\n", "0".repeat(127), "1".repeat(127));
+    let report = BitColumns::parse(&wide).unwrap();
+    assert_eq!(128, report.width());
+    assert_eq!(u128::MAX, report.mask());
+    assert_eq!(0, report.gamma());
+    assert_eq!(u128::MAX, report.epsilon());
+    assert_eq!(Ok(1 << 127), report.filter(most_common));
+    assert_eq!(Ok(u128::MAX >> 1), report.filter(least_common));
+}
+
+#[test]
+fn test_errors() {
+    let too_wide = "1".repeat(129);
+    assert_eq!(Err(BitsError::TooWide(129)), BitColumns::parse(&too_wide));
+    assert_eq!(Err(BitsError::Empty), BitColumns::parse("\n"));
+    assert_eq!(
+        "2:1: grid row is 2 wide, expected 3",
+        BitColumns::parse("101\n10\n").unwrap_err().to_string()
+    );
+    assert_eq!(
+        "1:3: unexpected trailing input \"2\"",
+        BitColumns::parse("102").unwrap_err().to_string()
+    );
+    let twins = BitColumns::new(vec![5, 5, 1], 3).unwrap();
+    assert_eq!(
+        "1:1: expected \"binary digit\"",
+        BitColumns::parse("x").unwrap_err().to_string()
+    );
+    assert_eq!(Err(BitsError::Ambiguous(2)), twins.filter(most_common));
+}
@@ -0,0 +1,223 @@
+/*
+Cycle detection for simulations that must be run far too many times.
+
+`detect` remembers every state it has seen (by fingerprint) and also hands
+back the states themselves, so anything derived from them can be
+extrapolated. `brent` finds the same cycle without the memory, when only the
+cycle's shape is needed.
+*/
+
+use std::{collections::HashMap, hash::Hash};
+
+/// The state at step `start + length` is the same as at step `start`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    pub start: usize,
+    pub length: usize,
+}
+
+impl Cycle {
+    /// The earliest step whose state matches step `n`.
+    pub fn equivalent_step(&self, n: u64) -> usize {
+        if n < self.start as u64 {
+            n as usize
+        } else {
+            self.start + ((n - self.start as u64) % self.length as u64) as usize
+        }
+    }
+
+    /// The value at step `n` of something that changes by the same amount
+    /// every trip around the cycle, like a running total. `value(i)` is
+    /// only asked for steps up to `start + length`.
+    pub fn extrapolate<F>(&self, n: u64, value: F) -> i128
+    where
+        F: Fn(usize) -> i128,
+    {
+        let base = self.equivalent_step(n);
+        if n < self.start as u64 {
+            return value(base);
+        }
+        let laps = (n - self.start as u64) / self.length as u64;
+        let per_lap = value(self.start + self.length) - value(self.start);
+        value(base) + laps as i128 * per_lap
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detected<S> {
+    pub cycle: Cycle,
+    /// Every state from the start through `cycle.start + cycle.length`.
+    pub states: Vec<S>,
+}
+
+impl<S> Detected<S> {
+    /// The state at step `n`, however far out that is.
+    pub fn state_at(&self, n: u64) -> &S {
+        &self.states[self.cycle.equivalent_step(n)]
+    }
+
+    /// `Cycle::extrapolate` with the value read from the recorded states.
+    pub fn extrapolate<F>(&self, n: u64, value: F) -> i128
+    where
+        F: Fn(&S) -> i128,
+    {
+        self.cycle.extrapolate(n, |i| value(&self.states[i]))
+    }
+}
+
+/// Runs `step` from `initial` until two states share a fingerprint, or
+/// gives up after `limit` steps. The fingerprint should capture everything
+/// that decides the future, and nothing that just accumulates (e.g. a rope's
+/// shape relative to its head, not where the head is).
+pub fn detect<S, K, F, G>(
+    initial: S,
+    mut step: F,
+    mut fingerprint: G,
+    limit: usize,
+) -> Option<Detected<S>>
+where
+    K: Hash + Eq,
+    F: FnMut(&S) -> S,
+    G: FnMut(&S) -> K,
+{
+    let mut seen = HashMap::new();
+    let mut states = vec![initial];
+    for n in 0..=limit {
+        let state = &states[n];
+        if let Some(start) = seen.insert(fingerprint(state), n) {
+            return Some(Detected {
+                cycle: Cycle {
+                    start,
+                    length: n - start,
+                },
+                states,
+            });
+        }
+        let next = step(state);
+        states.push(next);
+    }
+    None
+}
+
+/// `detect` for states that can be hashed directly.
+pub fn detect_hashed<S, F>(initial: S, step: F, limit: usize) -> Option<Detected<S>>
+where
+    S: Hash + Eq + Clone,
+    F: FnMut(&S) -> S,
+{
+    detect(initial, step, S::clone, limit)
+}
+
+/// Brent's algorithm: the same answer as `detect`, keeping only two states
+/// and two fingerprints in memory at a time.
+pub fn brent<S, K, F, G>(initial: S, mut step: F, mut fingerprint: G, limit: usize) -> Option<Cycle>
+where
+    S: Clone,
+    K: PartialEq,
+    F: FnMut(&S) -> S,
+    G: FnMut(&S) -> K,
+{
+    // Find the length: the hare runs ahead in power-of-two stretches, and
+    // the tortoise teleports to it at the end of each one.
+    let mut power = 1;
+    let mut length = 1;
+    let mut tortoise = fingerprint(&initial);
+    let mut hare_state = step(&initial);
+    let mut hare = fingerprint(&hare_state);
+    let mut steps = 1;
+    while tortoise != hare {
+        if steps > limit {
+            return None;
+        }
+        if power == length {
+            tortoise = hare;
+            power *= 2;
+            length = 0;
+        }
+        hare_state = step(&hare_state);
+        hare = fingerprint(&hare_state);
+        length += 1;
+        steps += 1;
+    }
+
+    // Find the start: two walkers `length` apart meet where the cycle begins.
+    let mut behind = initial.clone();
+    let mut ahead = initial;
+    for _ in 0..length {
+        ahead = step(&ahead);
+    }
+    let mut start = 0;
+    while fingerprint(&behind) != fingerprint(&ahead) {
+        behind = step(&behind);
+        ahead = step(&ahead);
+        start += 1;
+    }
+    Some(Cycle { start, length })
+}
+
+#[test]
+fn test_detect_rho() {
+    // 0 -> 1 -> 2 -> 3 -> 4 -> 5 -> 2 ...
+    let next = |n: &u32| if *n == 5 { 2 } else { n + 1 };
+    let found = detect_hashed(0, next, 100).unwrap();
+    assert_eq!(
+        Cycle {
+            start: 2,
+            length: 4
+        },
+        found.cycle
+    );
+    assert_eq!(vec![0, 1, 2, 3, 4, 5, 2], found.states);
+    assert_eq!(&3, found.state_at(1_000_000_000_003));
+    assert_eq!(Some(found.cycle), brent(0, next, |n| *n, 100));
+    assert_eq!(None, detect_hashed(0u32, |n| n + 1, 100));
+    assert_eq!(None, brent(0u32, |n| n + 1, |n| *n, 100));
+}
+
+#[test]
+fn test_lanternfish_parity() {
+    use crate::recurrence::Recurrence;
+
+    // Lanternfish buckets grow without bound, but their parities cycle, and
+    // the total can be checked against the recurrence engine.
+    let school = Recurrence::from_rule(9, |t| if t == 0 { vec![6, 8] } else { vec![t - 1] });
+    let start: Vec<u128> = vec![0, 1, 1, 2, 1, 0, 0, 0, 0];
+    let day = |fish: &Vec<u128>| school.advance_mod(fish, 1, 2);
+    let found = detect_hashed(start.clone(), day, 10_000).unwrap();
+    assert_eq!(
+        Some(found.cycle),
+        brent(start.clone(), day, Vec::clone, 10_000)
+    );
+
+    let n = 1_000_000_000_000;
+    let parity = found.state_at(n).iter().sum::<u128>() % 2;
+    let expected = school.advance_mod(&start, n, 2).iter().sum::<u128>() % 2;
+    assert_eq!(expected, parity);
+}
+
+#[test]
+fn test_extrapolate_running_total() {
+    // A counter that adds 3, 1, 4 forever after a one step lead-in of 10.
+    let adds = [10, 3, 1, 4];
+    let next = |(i, total): &(usize, i128)| {
+        let i = if *i == 3 { 1 } else { i + 1 };
+        (i, total + adds[i])
+    };
+    let found = detect((0usize, 10i128), next, |(i, _)| *i, 100).unwrap();
+    assert_eq!(
+        Cycle {
+            start: 1,
+            length: 3
+        },
+        found.cycle
+    );
+    let mut state = (0, 10);
+    for n in 0..50u64 {
+        assert_eq!(state.1, found.extrapolate(n, |s| s.1));
+        state = next(&state);
+    }
+    assert_eq!(
+        10 + 8 * 1_000_000_000,
+        found.extrapolate(3_000_000_000, |s| s.1)
+    );
+}
@@ -0,0 +1,547 @@
+/*
+Image files for sharing simulation state outside the terminal.
+
+A `Raster` is a box of RGB cells, made from a `SparseGrid` through a
+`Palette`, from a `viz::Frame`, or from a closure. One raster can be written
+as SVG or PPM, and a run of them as an animated GIF. Everything is encoded
+here, GIF's LZW compression included, so no outside tools are needed.
+*/
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fs::File,
+    hash::Hash,
+    io::{self, BufWriter, Write},
+    path::Path,
+    time::Duration,
+};
+
+use crate::{
+    grid::{Bounds, Point, SparseGrid},
+    viz::{Colour, Frame},
+};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub const BLACK: Rgb = Rgb(0, 0, 0);
+    pub const WHITE: Rgb = Rgb(255, 255, 255);
+
+    fn hex(self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+
+    /// The colour `t` of the way from `self` to `other`, `t` in `0.0..=1.0`.
+    pub fn blend(self, other: Rgb, t: f64) -> Rgb {
+        let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t).round() as u8;
+        Rgb(
+            mix(self.0, other.0),
+            mix(self.1, other.1),
+            mix(self.2, other.2),
+        )
+    }
+}
+
+impl From<Colour> for Rgb {
+    fn from(colour: Colour) -> Rgb {
+        match colour {
+            Colour::Red => Rgb(205, 49, 49),
+            Colour::Green => Rgb(13, 188, 121),
+            Colour::Yellow => Rgb(229, 229, 16),
+            Colour::Blue => Rgb(36, 114, 200),
+            Colour::Magenta => Rgb(188, 63, 188),
+            Colour::Cyan => Rgb(17, 168, 205),
+            Colour::White => Rgb(229, 229, 229),
+            Colour::Grey => Rgb(102, 102, 102),
+        }
+    }
+}
+
+/// Which colour each cell value is drawn in.
+#[derive(Debug, Clone)]
+pub struct Palette<T> {
+    colours: HashMap<T, Rgb>,
+    fallback: Rgb,
+    ends: Option<Ends<T>>,
+}
+
+/// A gradient's lowest and highest values, and how to compare others with
+/// them.
+#[derive(Debug, Clone)]
+struct Ends<T> {
+    lo: T,
+    hi: T,
+    cmp: fn(&T, &T) -> Ordering,
+}
+
+impl<T: Hash + Eq> Palette<T> {
+    /// Values without a colour of their own are drawn as `fallback`.
+    pub fn new(fallback: Rgb) -> Palette<T> {
+        Palette {
+            colours: HashMap::new(),
+            fallback,
+            ends: None,
+        }
+    }
+
+    pub fn with(mut self, value: T, colour: Rgb) -> Palette<T> {
+        self.colours.insert(value, colour);
+        self
+    }
+
+    /// Shades `values` evenly from `from` to `to`, in order, such as tree
+    /// heights 0 to 9. Values past either end take that end's colour.
+    pub fn gradient<I>(values: I, from: Rgb, to: Rgb) -> Palette<T>
+    where
+        I: IntoIterator<Item = T>,
+        T: Ord + Clone,
+    {
+        let values: Vec<T> = values.into_iter().collect();
+        let ends = match (values.iter().min(), values.iter().max()) {
+            (Some(lo), Some(hi)) => Some(Ends {
+                lo: lo.clone(),
+                hi: hi.clone(),
+                cmp: T::cmp,
+            }),
+            _ => None,
+        };
+        let last = values.len().saturating_sub(1).max(1) as f64;
+        let colours = values
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| (v, from.blend(to, i as f64 / last)))
+            .collect();
+        Palette {
+            colours,
+            fallback: from,
+            ends,
+        }
+    }
+
+    pub fn colour(&self, value: &T) -> Rgb {
+        if let Some(&colour) = self.colours.get(value) {
+            return colour;
+        }
+        match &self.ends {
+            Some(ends) if (ends.cmp)(value, &ends.lo).is_lt() => self.colours[&ends.lo],
+            Some(ends) if (ends.cmp)(value, &ends.hi).is_gt() => self.colours[&ends.hi],
+            _ => self.fallback,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Raster {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Raster {
+    pub fn from_fn<F>(bounds: Bounds, mut colour: F) -> Raster
+    where
+        F: FnMut(Point) -> Rgb,
+    {
+        let mut pixels = Vec::new();
+        for y in bounds.min_y..=bounds.max_y {
+            for x in bounds.min_x..=bounds.max_x {
+                pixels.push(colour((x, y)));
+            }
+        }
+        Raster {
+            width: bounds.width() as usize,
+            height: bounds.height() as usize,
+            pixels,
+        }
+    }
+
+    /// The grid's bounding box, coloured through `palette`.
+    pub fn from_grid<T: Hash + Eq>(grid: &SparseGrid<T>, palette: &Palette<T>) -> Raster {
+        let bounds = grid.bounds().unwrap_or(Bounds::around((0, 0)));
+        Raster::from_fn(bounds, |p| palette.colour(grid.get(p)))
+    }
+
+    /// Coloured cells keep their colour. Of the rest, blanks (`.` and space)
+    /// are black and anything else is white.
+    pub fn from_frame(frame: &Frame) -> Raster {
+        Raster::from_fn(frame.bounds(), |p| {
+            let cell = frame.get(p).unwrap();
+            match (cell.colour, cell.glyph) {
+                (Some(colour), _) => colour.into(),
+                (None, '.' | ' ') => Rgb::BLACK,
+                (None, _) => Rgb::WHITE,
+            }
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    fn rows(&self) -> impl Iterator<Item = &[Rgb]> {
+        self.pixels.chunks(self.width.max(1))
+    }
+
+    /// One `scale`-sided square per cell, with runs of the same colour along
+    /// a row merged into one rectangle.
+    pub fn write_svg(&self, out: &mut impl Write, scale: usize) -> io::Result<()> {
+        let (w, h) = (self.width * scale, self.height * scale);
+        writeln!(
+            out,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" shape-rendering="crispEdges">"#
+        )?;
+        for (y, row) in self.rows().enumerate() {
+            let mut x = 0;
+            for run in row.chunk_by(|a, b| a == b) {
+                writeln!(
+                    out,
+                    r#"<rect x="{}" y="{}" width="{}" height="{}" fill="{}"/>"#,
+                    x * scale,
+                    y * scale,
+                    run.len() * scale,
+                    scale,
+                    run[0].hex()
+                )?;
+                x += run.len();
+            }
+        }
+        writeln!(out, "</svg>")
+    }
+
+    /// Binary PPM (P6), each cell a `scale`-sided square.
+    pub fn write_ppm(&self, out: &mut impl Write, scale: usize) -> io::Result<()> {
+        write!(
+            out,
+            "P6\n{} {}\n255\n",
+            self.width * scale,
+            self.height * scale
+        )?;
+        for row in self.rows() {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|px| std::iter::repeat_n([px.0, px.1, px.2], scale))
+                .flatten()
+                .collect();
+            for _ in 0..scale {
+                out.write_all(&line)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn save_svg(&self, path: impl AsRef<Path>, scale: usize) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_svg(&mut out, scale)?;
+        out.flush()
+    }
+
+    pub fn save_ppm(&self, path: impl AsRef<Path>, scale: usize) -> io::Result<()> {
+        let mut out = BufWriter::new(File::create(path)?);
+        self.write_ppm(&mut out, scale)?;
+        out.flush()
+    }
+}
+
+/// An animated GIF that loops forever, showing each raster for `delay`.
+/// GIF allows 256 colours in all; more than that is an `InvalidInput` error.
+pub fn write_gif(
+    frames: &[Raster],
+    out: &mut impl Write,
+    scale: usize,
+    delay: Duration,
+) -> io::Result<()> {
+    let mut palette: Vec<Rgb> = Vec::new();
+    let mut index: HashMap<Rgb, u8> = HashMap::new();
+    for px in frames.iter().flat_map(|f| &f.pixels) {
+        if !index.contains_key(px) {
+            if palette.len() == 256 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "GIF frames use more than 256 colours",
+                ));
+            }
+            index.insert(*px, palette.len() as u8);
+            palette.push(*px);
+        }
+    }
+    // The colour table holds 2^bits entries, with at least 2 bits for LZW.
+    let bits = (usize::BITS - palette.len().saturating_sub(1).leading_zeros()).max(2);
+    palette.resize(1 << bits, Rgb::BLACK);
+
+    let dimension = |n: usize| {
+        u16::try_from(n * scale)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "GIF is too big"))
+    };
+    let width = dimension(frames.iter().map(|f| f.width).max().unwrap_or(0))?;
+    let height = dimension(frames.iter().map(|f| f.height).max().unwrap_or(0))?;
+
+    out.write_all(b"GIF89a")?;
+    out.write_all(&width.to_le_bytes())?;
+    out.write_all(&height.to_le_bytes())?;
+    // Global colour table present, 8 bits per primary, table size.
+    out.write_all(&[0x80 | 0x70 | (bits as u8 - 1), 0, 0])?;
+    for c in &palette {
+        out.write_all(&[c.0, c.1, c.2])?;
+    }
+    // Loop forever.
+    out.write_all(b"\x21\xff\x0bNETSCAPE2.0\x03\x01\x00\x00\x00")?;
+
+    let centiseconds = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+    for frame in frames {
+        out.write_all(&[0x21, 0xf9, 0x04, 0x00])?;
+        out.write_all(&centiseconds.to_le_bytes())?;
+        out.write_all(&[0x00, 0x00])?;
+
+        out.write_all(&[0x2c, 0, 0, 0, 0])?;
+        out.write_all(&dimension(frame.width)?.to_le_bytes())?;
+        out.write_all(&dimension(frame.height)?.to_le_bytes())?;
+        out.write_all(&[0x00])?;
+
+        let mut indices = Vec::with_capacity(frame.pixels.len() * scale * scale);
+        for row in frame.rows() {
+            let line: Vec<u8> = row
+                .iter()
+                .flat_map(|px| std::iter::repeat_n(index[px], scale))
+                .collect();
+            for _ in 0..scale {
+                indices.extend_from_slice(&line);
+            }
+        }
+        out.write_all(&[bits as u8])?;
+        for block in lzw_encode(&indices, bits as u8).chunks(255) {
+            out.write_all(&[block.len() as u8])?;
+            out.write_all(block)?;
+        }
+        out.write_all(&[0x00])?;
+    }
+    out.write_all(&[0x3b])
+}
+
+pub fn save_gif(
+    frames: &[Raster],
+    path: impl AsRef<Path>,
+    scale: usize,
+    delay: Duration,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(File::create(path)?);
+    write_gif(frames, &mut out, scale, delay)?;
+    out.flush()
+}
+
+/// Packs variable-width codes least significant bit first, as GIF wants.
+#[derive(Default)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    used: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, width: u8) {
+        self.buffer |= (code as u32) << self.used;
+        self.used += width;
+        while self.used >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.used -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.used > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    const MAX_CODES: u16 = 4096;
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+    let mut out = BitWriter::default();
+    let mut table: HashMap<(u16, u8), u16> = HashMap::new();
+    let mut width = min_code_size + 1;
+    let mut next = end + 1;
+
+    out.write(clear, width);
+    let Some((&first, rest)) = indices.split_first() else {
+        out.write(end, width);
+        return out.finish();
+    };
+    let mut prefix = first as u16;
+    for &k in rest {
+        if let Some(&code) = table.get(&(prefix, k)) {
+            prefix = code;
+            continue;
+        }
+        out.write(prefix, width);
+        if next < MAX_CODES {
+            table.insert((prefix, k), next);
+            next += 1;
+            // The decoder adds each entry one code later than we do, so it
+            // widens once our next free code passes its limit.
+            if next > 1 << width && width < 12 {
+                width += 1;
+            }
+        } else {
+            out.write(clear, width);
+            table.clear();
+            width = min_code_size + 1;
+            next = end + 1;
+        }
+        prefix = k as u16;
+    }
+    out.write(prefix, width);
+    out.write(end, width);
+    out.finish()
+}
+
+#[cfg(test)]
+fn heights() -> SparseGrid<u8> {
+    "30373\n25512\n65332\n33549\n35390"
+        .lines()
+        .enumerate()
+        .flat_map(|(y, line)| {
+            line.bytes()
+                .enumerate()
+                .map(move |(x, b)| ((x as i64, y as i64), b - b'0'))
+        })
+        .collect()
+}
+
+#[test]
+fn test_palette() {
+    let grey = Palette::gradient(0..=9u8, Rgb::BLACK, Rgb::WHITE);
+    assert_eq!(Rgb::BLACK, grey.colour(&0));
+    assert_eq!(Rgb(142, 142, 142), grey.colour(&5));
+    assert_eq!(Rgb::WHITE, grey.colour(&9));
+    assert_eq!(Rgb::WHITE, grey.colour(&42));
+    let heat = Palette::gradient(1..=3u32, Rgb::BLACK, Rgb::WHITE).with(0, Rgb(0, 0, 255));
+    assert_eq!(Rgb::WHITE, heat.colour(&7));
+    assert_eq!(Rgb(0, 0, 255), heat.colour(&0));
+    let sparse = Palette::gradient([2, 4, 6], Rgb::BLACK, Rgb::WHITE);
+    assert_eq!(Rgb::BLACK, sparse.colour(&-1));
+    assert_eq!(Rgb::BLACK, sparse.colour(&3));
+    assert_eq!(Rgb::WHITE, sparse.colour(&8));
+    let vents = Palette::new(Rgb::BLACK)
+        .with(1, Rgb::WHITE)
+        .with(2, Rgb(255, 0, 0));
+    assert_eq!(Rgb(255, 0, 0), vents.colour(&2));
+    assert_eq!(Rgb::BLACK, vents.colour(&7));
+}
+
+#[test]
+fn test_svg() {
+    let raster = Raster::from_frame(&Frame::from_points([(0, 0), (1, 0), (3, 1)], '#'));
+    let mut out = Vec::new();
+    raster.write_svg(&mut out, 10).unwrap();
+    let svg = String::from_utf8(out).unwrap();
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"40\" height=\"20\""));
+    assert!(svg.contains(r##"<rect x="0" y="0" width="20" height="10" fill="#ffffff"/>"##));
+    assert!(svg.contains(r##"<rect x="20" y="0" width="20" height="10" fill="#000000"/>"##));
+    assert!(svg.contains(r##"<rect x="30" y="10" width="10" height="10" fill="#ffffff"/>"##));
+    assert_eq!(4, svg.matches("<rect").count());
+}
+
+#[test]
+fn test_ppm() {
+    let raster = Raster::from_fn(Bounds::around((0, 0)), |_| Rgb(1, 2, 3));
+    let mut out = Vec::new();
+    raster.write_ppm(&mut out, 2).unwrap();
+    let mut expected = b"P6\n2 2\n255\n".to_vec();
+    expected.extend([1, 2, 3].repeat(4));
+    assert_eq!(expected, out);
+}
+
+#[cfg(test)]
+fn decode_gif(bytes: &[u8]) -> Vec<(u16, u16, Vec<u8>, u16)> {
+    let mut options = gif::DecodeOptions::new();
+    options.set_color_output(gif::ColorOutput::RGBA);
+    let mut decoder = options.read_info(bytes).unwrap();
+    let mut frames = Vec::new();
+    while let Some(frame) = decoder.read_next_frame().unwrap() {
+        frames.push((
+            frame.width,
+            frame.height,
+            frame.buffer.to_vec(),
+            frame.delay,
+        ));
+    }
+    frames
+}
+
+#[test]
+fn test_gif_round_trip() {
+    let grey = Palette::gradient(0..=9u8, Rgb::BLACK, Rgb::WHITE);
+    let trees = Raster::from_grid(&heights(), &grey);
+    let dark = Raster::from_fn(Bounds::around((0, 0)), |_| Rgb::BLACK);
+    let mut out = Vec::new();
+    write_gif(
+        &[trees.clone(), dark],
+        &mut out,
+        3,
+        Duration::from_millis(250),
+    )
+    .unwrap();
+
+    let frames = decode_gif(&out);
+    assert_eq!(2, frames.len());
+    let (width, height, rgba, delay) = &frames[0];
+    assert_eq!((15, 15, 25), (*width, *height, *delay));
+    for y in 0..15 {
+        for x in 0..15 {
+            let px = &rgba[(y * 15 + x) * 4..][..3];
+            let want = trees.get(x / 3, y / 3);
+            assert_eq!([want.0, want.1, want.2], px);
+        }
+    }
+    assert_eq!((3, 3), (frames[1].0, frames[1].1));
+}
+
+#[test]
+fn test_lzw_table_resets() {
+    // Enough noise to fill the 4096 entry code table several times over.
+    let mut seed = 12345u32;
+    let indices: Vec<u8> = (0..200_000)
+        .map(|_| {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) as u8
+        })
+        .collect();
+    let raster = Raster {
+        width: 500,
+        height: 400,
+        pixels: indices.iter().map(|&i| Rgb(i, i, i)).collect(),
+    };
+    let mut out = Vec::new();
+    write_gif(std::slice::from_ref(&raster), &mut out, 1, Duration::ZERO).unwrap();
+    let frames = decode_gif(&out);
+    let grey: Vec<u8> = frames[0].2.chunks(4).map(|px| px[0]).collect();
+    assert_eq!(indices, grey);
+}
+
+#[test]
+fn test_gif_colour_limit() {
+    let raster = Raster::from_fn(
+        Bounds {
+            min_x: 0,
+            min_y: 0,
+            max_x: 256,
+            max_y: 0,
+        },
+        |(x, _)| Rgb((x % 256) as u8, (x / 256) as u8, 0),
+    );
+    let err = write_gif(&[raster], &mut Vec::new(), 1, Duration::ZERO).unwrap_err();
+    assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+}
@@ -0,0 +1,230 @@
+/*
+A grid for coordinates that don't fit a dense array.
+
+Cells live in a hash map keyed by `(x, y)`, so negative coordinates and ones
+in the millions cost nothing until they are used. Unset cells read as the
+grid's default, and the bounding box of everything set is kept up to date
+for rendering.
+*/
+
+use std::{collections::HashMap, fmt};
+
+pub type Point = (i64, i64);
+
+/// An inclusive bounding box.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Bounds {
+    pub min_x: i64,
+    pub min_y: i64,
+    pub max_x: i64,
+    pub max_y: i64,
+}
+
+impl Bounds {
+    pub fn around(p: Point) -> Bounds {
+        Bounds {
+            min_x: p.0,
+            min_y: p.1,
+            max_x: p.0,
+            max_y: p.1,
+        }
+    }
+
+    /// How many columns, which is `2^64` if the box spans every `i64`.
+    pub fn width(&self) -> u128 {
+        self.min_x.abs_diff(self.max_x) as u128 + 1
+    }
+
+    pub fn height(&self) -> u128 {
+        self.min_y.abs_diff(self.max_y) as u128 + 1
+    }
+
+    pub fn contains(&self, p: Point) -> bool {
+        (self.min_x..=self.max_x).contains(&p.0) && (self.min_y..=self.max_y).contains(&p.1)
+    }
+
+    /// Grows the box to take in `p`.
+    pub fn include(&mut self, p: Point) {
+        self.min_x = self.min_x.min(p.0);
+        self.min_y = self.min_y.min(p.1);
+        self.max_x = self.max_x.max(p.0);
+        self.max_y = self.max_y.max(p.1);
+    }
+
+    fn on_edge(&self, p: Point) -> bool {
+        p.0 == self.min_x || p.0 == self.max_x || p.1 == self.min_y || p.1 == self.max_y
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: HashMap<Point, T>,
+    default: T,
+    bounds: Option<Bounds>,
+}
+
+impl<T> SparseGrid<T> {
+    /// An empty grid where every cell reads as `default`.
+    pub fn new(default: T) -> SparseGrid<T> {
+        SparseGrid {
+            cells: HashMap::new(),
+            default,
+            bounds: None,
+        }
+    }
+
+    pub fn get(&self, p: Point) -> &T {
+        self.cells.get(&p).unwrap_or(&self.default)
+    }
+
+    pub fn is_set(&self, p: Point) -> bool {
+        self.cells.contains_key(&p)
+    }
+
+    pub fn set(&mut self, p: Point, value: T) -> Option<T> {
+        self.include(p);
+        self.cells.insert(p, value)
+    }
+
+    /// Unsets a cell, so it reads as the default again.
+    pub fn remove(&mut self, p: Point) -> Option<T> {
+        let removed = self.cells.remove(&p)?;
+        if self.bounds.is_some_and(|b| b.on_edge(p)) {
+            self.bounds = self.cells.keys().fold(None, |bounds, &p| match bounds {
+                None => Some(Bounds::around(p)),
+                Some(mut b) => {
+                    b.include(p);
+                    Some(b)
+                }
+            });
+        }
+        Some(removed)
+    }
+
+    /// The bounding box of every set cell, or `None` for an empty grid.
+    pub fn bounds(&self) -> Option<Bounds> {
+        self.bounds
+    }
+
+    /// How many cells are set.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    /// The set cells, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (Point, &T)> {
+        self.cells.iter().map(|(p, v)| (*p, v))
+    }
+
+    fn include(&mut self, p: Point) {
+        match &mut self.bounds {
+            None => self.bounds = Some(Bounds::around(p)),
+            Some(b) => b.include(p),
+        }
+    }
+
+    /// Draws the bounding box one row per line, top row (smallest `y`)
+    /// first, with `draw` picking each cell's character.
+    pub fn render<F>(&self, mut draw: F) -> String
+    where
+        F: FnMut(&T) -> char,
+    {
+        let Some(b) = self.bounds else {
+            return String::new();
+        };
+        let mut out = String::new();
+        for y in b.min_y..=b.max_y {
+            for x in b.min_x..=b.max_x {
+                out.push(draw(self.get((x, y))));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl<T: Clone> SparseGrid<T> {
+    /// A mutable reference to the cell, setting it to the default first if
+    /// it wasn't set.
+    pub fn get_mut(&mut self, p: Point) -> &mut T {
+        self.include(p);
+        self.cells.entry(p).or_insert_with(|| self.default.clone())
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for SparseGrid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(b) = self.bounds else {
+            return Ok(());
+        };
+        for y in b.min_y..=b.max_y {
+            for x in b.min_x..=b.max_x {
+                write!(f, "{}", self.get((x, y)))?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: Default> FromIterator<(Point, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point, T)>>(iter: I) -> SparseGrid<T> {
+        let mut grid = SparseGrid::new(T::default());
+        for (p, v) in iter {
+            grid.set(p, v);
+        }
+        grid
+    }
+}
+
+#[test]
+fn test_far_apart_cells() {
+    let mut grid = SparseGrid::new(0u32);
+    *grid.get_mut((-3_000_000, 7)) += 1;
+    *grid.get_mut((5_000_000, -2)) += 2;
+    *grid.get_mut((5_000_000, -2)) += 2;
+    assert_eq!(2, grid.len());
+    assert_eq!(&4, grid.get((5_000_000, -2)));
+    assert_eq!(&0, grid.get((0, 0)));
+    let b = grid.bounds().unwrap();
+    assert_eq!((8_000_001, 10), (b.width(), b.height()));
+    assert!(b.contains((0, 0)));
+    let mut everywhere = Bounds::around((i64::MIN, 0));
+    everywhere.include((i64::MAX, 0));
+    assert_eq!((1 << 64, 1), (everywhere.width(), everywhere.height()));
+}
+
+#[test]
+fn test_remove_shrinks_bounds() {
+    let mut grid: SparseGrid<char> = [((0, 0), 'a'), ((2, 1), 'b'), ((1, 5), 'c')]
+        .into_iter()
+        .collect();
+    assert_eq!(None, grid.remove((9, 9)));
+    assert_eq!(Some('c'), grid.remove((1, 5)));
+    assert_eq!(
+        Some(Bounds {
+            min_x: 0,
+            min_y: 0,
+            max_x: 2,
+            max_y: 1
+        }),
+        grid.bounds()
+    );
+    grid.remove((0, 0));
+    grid.remove((2, 1));
+    assert_eq!(None, grid.bounds());
+}
+
+#[test]
+fn test_render() {
+    let mut grid = SparseGrid::new('.');
+    grid.set((-1, -1), '#');
+    grid.set((1, 0), '#');
+    assert_eq!("#..\n..#\n", grid.render(|c| *c));
+    assert_eq!("#..\n..#\n", grid.to_string());
+    assert_eq!("", SparseGrid::new('.').render(|c| *c));
+}
@@ -0,0 +1,23 @@
+/*
+Shared helpers for the Advent of Code solutions in this repository.
+
+Each day lives in its own crate, so anything more than one day needs goes
+here and gets pulled in as a path dependency.
+*/
+
+pub mod align;
+pub mod bingo;
+pub mod bits;
+pub mod cycle;
+pub mod export;
+pub mod grid;
+pub mod math;
+pub mod multiset;
+pub mod parse;
+pub mod recurrence;
+pub mod rps;
+pub mod segment;
+pub mod solve;
+pub mod submarine;
+pub mod viz;
+pub mod window;
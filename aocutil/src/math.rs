@@ -0,0 +1,385 @@
+/*
+Number theory for cycle-alignment and modular puzzles.
+
+Everything takes any of the primitive integer types. The modular functions
+do their work in u128 with an overflow-safe multiply, so moduli can use the
+full width of whatever type they come in as.
+*/
+
+use std::{
+    fmt::Debug,
+    ops::{Add, Div, Mul, Neg, Rem, Sub},
+};
+
+pub trait Int:
+    Copy
+    + Ord
+    + Debug
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Rem<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    /// `Self::MAX` widened to u128.
+    const MAX_U128: u128;
+
+    fn checked_mul(self, rhs: Self) -> Option<Self>;
+    /// The value as a u128, or `None` if it is negative.
+    fn to_u128(self) -> Option<u128>;
+    /// The absolute value as a u128, which always fits, even for `MIN`.
+    fn magnitude(self) -> u128;
+    /// `None` if `n` does not fit.
+    fn from_u128(n: u128) -> Option<Self>;
+    /// The least non-negative residue of `self` modulo `m`, where `m` is a
+    /// positive value of this type widened to u128.
+    fn residue(self, m: u128) -> u128;
+}
+
+macro_rules! impl_unsigned {
+    ($($t:ty)*) => {$(
+        impl Int for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const MAX_U128: u128 = <$t>::MAX as u128;
+
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_mul(self, rhs)
+            }
+
+            fn to_u128(self) -> Option<u128> {
+                Some(self as u128)
+            }
+
+            fn magnitude(self) -> u128 {
+                self as u128
+            }
+
+            fn from_u128(n: u128) -> Option<Self> {
+                n.try_into().ok()
+            }
+
+            fn residue(self, m: u128) -> u128 {
+                self as u128 % m
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_signed {
+    ($($t:ty)*) => {$(
+        impl Int for $t {
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+            const MAX_U128: u128 = <$t>::MAX as u128;
+
+            fn checked_mul(self, rhs: Self) -> Option<Self> {
+                <$t>::checked_mul(self, rhs)
+            }
+
+            fn to_u128(self) -> Option<u128> {
+                self.try_into().ok()
+            }
+
+            fn magnitude(self) -> u128 {
+                self.unsigned_abs() as u128
+            }
+
+            fn from_u128(n: u128) -> Option<Self> {
+                n.try_into().ok()
+            }
+
+            fn residue(self, m: u128) -> u128 {
+                // m came from a positive value of this type, so it fits i128.
+                (self as i128).rem_euclid(m as i128) as u128
+            }
+        }
+    )*};
+}
+
+impl_unsigned!(u8 u16 u32 u64 u128 usize);
+impl_signed!(i8 i16 i32 i64 i128 isize);
+
+fn gcd_u128(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// Greatest common divisor, always non-negative. `gcd(0, 0)` is 0.
+///
+/// Panics if the answer doesn't fit `T`, which only happens for a signed
+/// `MIN` with 0 or with itself; `checked_gcd` returns `None` instead.
+pub fn gcd<T: Int>(a: T, b: T) -> T {
+    checked_gcd(a, b).expect("gcd is -MIN, which doesn't fit")
+}
+
+/// `gcd`, or `None` if the answer is too big for `T`.
+pub fn checked_gcd<T: Int>(a: T, b: T) -> Option<T> {
+    // Magnitudes always fit in u128, so MIN % -1 never comes up.
+    T::from_u128(gcd_u128(a.magnitude(), b.magnitude()))
+}
+
+/// Least common multiple, or `None` if it overflows `T`.
+pub fn lcm<T: Int>(a: T, b: T) -> Option<T> {
+    if a == T::ZERO || b == T::ZERO {
+        return Some(T::ZERO);
+    }
+    let (a, b) = (a.magnitude(), b.magnitude());
+    T::from_u128((a / gcd_u128(a, b)).checked_mul(b)?)
+}
+
+pub fn gcd_all<T: Int>(nums: impl IntoIterator<Item = T>) -> T {
+    nums.into_iter().fold(T::ZERO, gcd)
+}
+
+/// `None` if the running lcm overflows. An empty list gives 1.
+pub fn lcm_all<T: Int>(nums: impl IntoIterator<Item = T>) -> Option<T> {
+    nums.into_iter().try_fold(T::ONE, |acc, n| lcm(acc, n))
+}
+
+/// Returns `(g, x, y)` with `a*x + b*y == g == gcd(a, b)`.
+pub fn ext_gcd<T: Int + Neg<Output = T>>(a: T, b: T) -> (T, T, T) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (T::ONE, T::ZERO);
+    let (mut y0, mut y1) = (T::ZERO, T::ONE);
+    while r1 != T::ZERO {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < T::ZERO {
+        (-r0, -x0, -y0)
+    } else {
+        (r0, x0, y0)
+    }
+}
+
+fn modulus<T: Int>(m: T) -> u128 {
+    match m.to_u128() {
+        Some(m) if m > 0 => m,
+        _ => panic!("modulus must be positive, got {:?}", m),
+    }
+}
+
+pub(crate) fn add_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= m - b {
+        a - (m - b)
+    } else {
+        a + b
+    }
+}
+
+fn sub_mod(a: u128, b: u128, m: u128) -> u128 {
+    if a >= b {
+        a - b
+    } else {
+        m - (b - a)
+    }
+}
+
+pub(crate) fn mul_mod(a: u128, b: u128, m: u128) -> u128 {
+    if let Some(product) = a.checked_mul(b) {
+        return product % m;
+    }
+    // Double-and-add so no intermediate ever exceeds m.
+    let (mut a, mut b, mut product) = (a % m, b, 0);
+    while b > 0 {
+        if b & 1 == 1 {
+            product = add_mod(product, a, m);
+        }
+        a = add_mod(a, a, m);
+        b >>= 1;
+    }
+    product
+}
+
+fn pow_mod(base: u128, mut exp: u128, m: u128) -> u128 {
+    let mut base = base % m;
+    let mut result = 1 % m;
+    while exp > 0 {
+        if exp & 1 == 1 {
+            result = mul_mod(result, base, m);
+        }
+        base = mul_mod(base, base, m);
+        exp >>= 1;
+    }
+    result
+}
+
+fn inv_mod(a: u128, m: u128) -> Option<u128> {
+    // Extended Euclid with the coefficient kept reduced mod m, so it never
+    // needs a sign.
+    let (mut r0, mut r1) = (m, a % m);
+    let (mut t0, mut t1) = (0, 1 % m);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (t0, t1) = (t1, sub_mod(t0, mul_mod(q % m, t1, m), m));
+    }
+    (r0 == 1).then_some(t0)
+}
+
+/// `base^exp mod modulus`, in `0..modulus`. Panics if `exp` is negative or
+/// `modulus` is not positive.
+pub fn modpow<T: Int>(base: T, exp: T, modulus: T) -> T {
+    let m = self::modulus(modulus);
+    let exp = exp
+        .to_u128()
+        .unwrap_or_else(|| panic!("negative exponent {:?}", exp));
+    T::from_u128(pow_mod(base.residue(m), exp, m)).expect("residue is below the modulus")
+}
+
+/// The `x` in `0..modulus` with `a*x ≡ 1`, if `a` and `modulus` are coprime.
+pub fn modinv<T: Int>(a: T, modulus: T) -> Option<T> {
+    let m = self::modulus(modulus);
+    inv_mod(a.residue(m), m).and_then(T::from_u128)
+}
+
+/// Solves a system of congruences `x ≡ a (mod m)`, which need not have
+/// coprime moduli. Returns `(x, lcm)` with `x` in `0..lcm`, or `None` if the
+/// system is inconsistent or the combined modulus overflows `T`.
+pub fn crt<T: Int>(congruences: impl IntoIterator<Item = (T, T)>) -> Option<(T, T)> {
+    let (mut x, mut m1) = (0u128, 1u128);
+    for (a, m) in congruences {
+        let m2 = modulus(m);
+        let g = gcd(m1, m2);
+        let diff = sub_mod(a.residue(m2), x % m2, m2);
+        if !diff.is_multiple_of(g) {
+            return None;
+        }
+        let step = m2 / g;
+        let k = mul_mod(diff / g, inv_mod((m1 / g) % step, step)?, step);
+        let combined = m1.checked_mul(step).filter(|&l| l <= T::MAX_U128)?;
+        x = add_mod(x, mul_mod(m1, k, combined), combined);
+        m1 = combined;
+    }
+    Some((T::from_u128(x)?, T::from_u128(m1)?))
+}
+
+/// The floor of the square root. Panics on negative input.
+pub fn isqrt<T: Int>(n: T) -> T {
+    let n128 = n
+        .to_u128()
+        .unwrap_or_else(|| panic!("square root of negative {:?}", n));
+    if n128 < 2 {
+        return n;
+    }
+    // Newton's method from an overestimate converges down onto the floor.
+    let mut x = 1u128 << (128 - n128.leading_zeros()).div_ceil(2);
+    loop {
+        let next = (x + n128 / x) / 2;
+        if next >= x {
+            break;
+        }
+        x = next;
+    }
+    T::from_u128(x).expect("root is no bigger than n")
+}
+
+#[test]
+fn test_examples() {
+    assert_eq!(6, gcd(48u32, 18));
+    assert_eq!(6, gcd(-48i64, 18));
+    assert_eq!(Some(36), lcm(12i32, 18));
+    assert_eq!(None, lcm(200u8, 199));
+    assert_eq!(4, gcd_all([8usize, 12, 20]));
+    assert_eq!(Some(2520), lcm_all(1..=10u64));
+    assert_eq!((2, -9, 47), ext_gcd(240i64, 46));
+    assert_eq!(445, modpow(4u16, 13, 497));
+    assert_eq!(1, modpow(-1i32, 2, 5));
+    assert_eq!(Some(4), modinv(3i32, 11));
+    assert_eq!(None, modinv(6u32, 9));
+    assert_eq!(Some((23, 105)), crt([(2i64, 3), (3, 5), (2, 7)]));
+    // Non-coprime moduli: x ≡ 3 (mod 4), x ≡ 5 (mod 6)
+    assert_eq!(Some((11, 12)), crt([(3u32, 4), (5, 6)]));
+    assert_eq!(None, crt([(1u32, 4), (2, 6)]));
+    assert_eq!(None, crt([(0u8, 16), (0, 17)]));
+    assert_eq!(3, isqrt(15u8));
+    assert_eq!(u64::MAX as u128, isqrt(u128::MAX));
+}
+
+#[test]
+fn test_full_width_moduli() {
+    let m = u128::MAX - 158; // the largest 128-bit prime
+    assert_eq!(1, modpow(3, m - 1, m));
+    let inv = modinv(u128::MAX / 3, m).unwrap();
+    assert_eq!(1, mul_mod(inv, u128::MAX / 3, m));
+}
+
+#[cfg(test)]
+use proptest::prelude::*;
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn prop_gcd_divides(a in any::<i64>().prop_filter("abs", |a| *a != i64::MIN), b in any::<i64>().prop_filter("abs", |b| *b != i64::MIN)) {
+        let g = gcd(a, b);
+        prop_assert!(g >= 0);
+        if g != 0 {
+            prop_assert_eq!(0, a % g);
+            prop_assert_eq!(0, b % g);
+            prop_assert_eq!(1, gcd(a / g, b / g));
+        }
+    }
+
+    #[test]
+    fn prop_gcd_at_min(b in any::<i64>()) {
+        // Every divisor of MIN is a power of two.
+        let expected = match b {
+            0 | i64::MIN => None,
+            b => Some(1 << b.trailing_zeros()),
+        };
+        prop_assert_eq!(expected, checked_gcd(i64::MIN, b));
+        prop_assert_eq!(expected, checked_gcd(b, i64::MIN));
+        if b != 0 {
+            prop_assert_eq!(None, lcm(i64::MIN, b));
+        }
+    }
+
+    #[test]
+    fn prop_lcm_matches_gcd(a in 1u32..100_000, b in 1u32..100_000) {
+        let l = lcm(a as u64, b as u64).unwrap();
+        prop_assert_eq!(a as u64 * b as u64, l * gcd(a, b) as u64);
+    }
+
+    #[test]
+    fn prop_ext_gcd_bezout(a in -1_000_000i64..1_000_000, b in -1_000_000i64..1_000_000) {
+        let (g, x, y) = ext_gcd(a, b);
+        prop_assert_eq!(gcd(a, b), g);
+        prop_assert_eq!(g, a * x + b * y);
+    }
+
+    #[test]
+    fn prop_modpow_matches_repeated_multiply(base in any::<i32>(), exp in 0u32..64, m in 1i32..i32::MAX) {
+        let expected = (0..exp).fold(1 % m as i64, |acc, _| acc * base as i64 % m as i64).rem_euclid(m as i64);
+        prop_assert_eq!(expected as i32, modpow(base, exp as i32, m));
+    }
+
+    #[test]
+    fn prop_modinv(a in any::<u64>(), m in 2u64..) {
+        match modinv(a, m) {
+            Some(inv) => prop_assert_eq!(1, mul_mod(a as u128, inv as u128, m as u128)),
+            None => prop_assert_ne!(1, gcd(a % m, m)),
+        }
+    }
+
+    #[test]
+    fn prop_crt_solution(x in 0u64..1_000_000, moduli in proptest::collection::vec(1u64..1000, 1..5)) {
+        let system: Vec<_> = moduli.iter().map(|&m| (x % m, m)).collect();
+        let (solution, l) = crt(system).unwrap();
+        prop_assert_eq!(lcm_all(moduli.iter().copied()).unwrap(), l);
+        prop_assert_eq!(x % l, solution);
+    }
+
+    #[test]
+    fn prop_isqrt(n in any::<u64>()) {
+        let r = isqrt(n) as u128;
+        prop_assert!(r * r <= n as u128);
+        prop_assert!((r + 1) * (r + 1) > n as u128);
+    }
+}
@@ -0,0 +1,239 @@
+/*
+Multisets: sets that remember how many times each item was added.
+
+Items are kept in order, so walking a multiset gives every item smallest
+first along with its count. That makes pairing two lists off in sorted
+order a merge rather than two sorts, and similarity scores a single pass
+over the smaller set's distinct items.
+*/
+
+use std::{
+    collections::{btree_map, BTreeMap},
+    error, fmt,
+    iter::{self, FromIterator},
+};
+
+/// Two multisets that were meant to pair off item for item don't hold the
+/// same number of items.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct LengthMismatch {
+    pub left: usize,
+    pub right: usize,
+}
+
+impl fmt::Display for LengthMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "can't pair {} items on the left with {} on the right",
+            self.left, self.right
+        )
+    }
+}
+
+impl error::Error for LengthMismatch {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Multiset<T> {
+    counts: BTreeMap<T, usize>,
+    len: usize,
+}
+
+impl<T: Ord> Multiset<T> {
+    pub fn new() -> Multiset<T> {
+        Multiset {
+            counts: BTreeMap::new(),
+            len: 0,
+        }
+    }
+
+    pub fn insert(&mut self, item: T) {
+        self.insert_n(item, 1);
+    }
+
+    pub fn insert_n(&mut self, item: T, n: usize) {
+        if n > 0 {
+            *self.counts.entry(item).or_insert(0) += n;
+            self.len += n;
+        }
+    }
+
+    pub fn count(&self, item: &T) -> usize {
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    /// How many items, counting repeats.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// How many different items.
+    pub fn distinct(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// Each different item with its count, smallest first.
+    pub fn iter(&self) -> btree_map::Iter<'_, T, usize> {
+        self.counts.iter()
+    }
+
+    /// Every item, repeats included, smallest first.
+    pub fn sorted(&self) -> impl Iterator<Item = &T> {
+        self.counts
+            .iter()
+            .flat_map(|(item, &n)| iter::repeat_n(item, n))
+    }
+}
+
+impl<T: Ord + Clone> Multiset<T> {
+    /// The items both hold, as many times as the one with fewer has them.
+    pub fn intersection(&self, other: &Multiset<T>) -> Multiset<T> {
+        self.iter()
+            .map(|(item, &n)| (item, n.min(other.count(item))))
+            .collect()
+    }
+
+    /// What is left of this one after taking away everything in `other`.
+    pub fn difference(&self, other: &Multiset<T>) -> Multiset<T> {
+        self.iter()
+            .map(|(item, &n)| (item, n.saturating_sub(other.count(item))))
+            .collect()
+    }
+
+    /// Every item on this side, weighted by `weight`, times how often it
+    /// appears on the other: the sum of `weight(x) * self.count(x) *
+    /// other.count(x)`.
+    pub fn similarity<F>(&self, other: &Multiset<T>, weight: F) -> u64
+    where
+        F: Fn(&T) -> u64,
+    {
+        let (small, large) = if self.distinct() <= other.distinct() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        small
+            .iter()
+            .map(|(item, &n)| weight(item) * (n * large.count(item)) as u64)
+            .sum()
+    }
+
+    /// Pairs the smallest item on each side, then the next smallest and so
+    /// on, and adds up `distance` for every pair.
+    pub fn pairing_distance<F>(
+        &self,
+        other: &Multiset<T>,
+        distance: F,
+    ) -> Result<u64, LengthMismatch>
+    where
+        F: Fn(&T, &T) -> u64,
+    {
+        if self.len != other.len {
+            return Err(LengthMismatch {
+                left: self.len,
+                right: other.len,
+            });
+        }
+        Ok(self
+            .sorted()
+            .zip(other.sorted())
+            .map(|(a, b)| distance(a, b))
+            .sum())
+    }
+}
+
+impl<T: Ord> Default for Multiset<T> {
+    fn default() -> Multiset<T> {
+        Multiset::new()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for Multiset<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(items: I) -> Multiset<T> {
+        let mut set = Multiset::new();
+        for item in items {
+            set.insert(item);
+        }
+        set
+    }
+}
+
+impl<'a, T: Ord + Clone> FromIterator<(&'a T, usize)> for Multiset<T> {
+    fn from_iter<I: IntoIterator<Item = (&'a T, usize)>>(counts: I) -> Multiset<T> {
+        let mut set = Multiset::new();
+        for (item, n) in counts {
+            set.insert_n(item.clone(), n);
+        }
+        set
+    }
+}
+
+impl<T: Ord> Extend<T> for Multiset<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, items: I) {
+        for item in items {
+            self.insert(item);
+        }
+    }
+}
+
+#[cfg(test)]
+fn example() -> (Multiset<u64>, Multiset<u64>) {
+    (
+        [3, 4, 2, 1, 3, 3].into_iter().collect(),
+        [4, 3, 5, 3, 9, 3].into_iter().collect(),
+    )
+}
+
+#[test]
+fn test_location_lists() {
+    let (left, right) = example();
+    assert_eq!(6, left.len());
+    assert_eq!(4, left.distinct());
+    assert_eq!(3, right.count(&3));
+    assert_eq!(0, right.count(&1));
+    assert_eq!(
+        vec![1, 2, 3, 3, 3, 4],
+        left.sorted().copied().collect::<Vec<_>>()
+    );
+    assert_eq!(Ok(11), left.pairing_distance(&right, |a, b| a.abs_diff(*b)));
+    assert_eq!(31, left.similarity(&right, |&x| x));
+    assert_eq!(31, right.similarity(&left, |&x| x));
+}
+
+#[test]
+fn test_set_operations() {
+    let (left, right) = example();
+    let both = left.intersection(&right);
+    assert_eq!(vec![(&3, &3), (&4, &1)], both.iter().collect::<Vec<_>>());
+    assert_eq!(4, both.len());
+    let only_left = left.difference(&right);
+    assert_eq!(vec![1, 2], only_left.sorted().copied().collect::<Vec<_>>());
+    assert_eq!(
+        vec![5, 9],
+        right
+            .difference(&left)
+            .sorted()
+            .copied()
+            .collect::<Vec<_>>()
+    );
+    assert!(left.difference(&left).is_empty());
+    assert_eq!(0, left.difference(&left).distinct());
+}
+
+#[test]
+fn test_unequal_lengths() {
+    let (mut left, right) = example();
+    left.extend([7, 7]);
+    assert_eq!(
+        Err(LengthMismatch { left: 8, right: 6 }),
+        left.pairing_distance(&right, |a, b| a.abs_diff(*b))
+    );
+    assert_eq!(
+        "can't pair 8 items on the left with 6 on the right",
+        LengthMismatch { left: 8, right: 6 }.to_string()
+    );
+}
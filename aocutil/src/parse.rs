@@ -0,0 +1,483 @@
+/*
+Small parser combinators for puzzle input.
+
+Everything is built on `Cursor`, which walks a `&str` and remembers where it
+is so that failures come back as a `ParseError` carrying a line and column
+instead of a bare `ParseIntError` or a panic.
+*/
+
+use std::{error, fmt, str::FromStr};
+
+/// 1-based line and column of a point in the input.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A literal was expected but something else was found.
+    Expected(String),
+    /// No digits where an integer should be.
+    InvalidInteger,
+    /// The digits were fine but do not fit the target type.
+    IntegerOverflow,
+    /// A `key: value` line had no separator.
+    MissingSeparator(String),
+    /// A grid row was a different width than the first row.
+    RaggedGrid { expected: usize, found: usize },
+    /// The item parsed, but there was input left over.
+    TrailingInput(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub position: Position,
+    pub kind: ErrorKind,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: ", self.position)?;
+        match &self.kind {
+            ErrorKind::Expected(what) => write!(f, "expected {:?}", what),
+            ErrorKind::InvalidInteger => write!(f, "expected an integer"),
+            ErrorKind::IntegerOverflow => write!(f, "integer out of range"),
+            ErrorKind::MissingSeparator(sep) => write!(f, "missing separator {:?}", sep),
+            ErrorKind::RaggedGrid { expected, found } => {
+                write!(f, "grid row is {} wide, expected {}", found, expected)
+            }
+            ErrorKind::TrailingInput(rest) => write!(f, "unexpected trailing input {:?}", rest),
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+pub type Result<T> = std::result::Result<T, ParseError>;
+
+#[derive(Debug, Clone)]
+pub struct Cursor<'a> {
+    input: &'a str,
+    offset: usize,
+    first_line: usize,
+}
+
+impl<'a> Cursor<'a> {
+    pub fn new(input: &'a str) -> Cursor<'a> {
+        Cursor::at_line(input, 1)
+    }
+
+    /// A cursor over a slice that starts on `line` of some larger input, so
+    /// positions in errors refer to the original file.
+    pub fn at_line(input: &'a str, line: usize) -> Cursor<'a> {
+        Cursor {
+            input,
+            offset: 0,
+            first_line: line,
+        }
+    }
+
+    /// Byte offset from the start of this cursor's input.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn rest(&self) -> &'a str {
+        &self.input[self.offset..]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.offset == self.input.len()
+    }
+
+    pub fn position(&self) -> Position {
+        let consumed = &self.input[..self.offset];
+        let line = self.first_line + consumed.matches('\n').count();
+        let line_start = consumed.rfind('\n').map_or(0, |n| n + 1);
+        Position {
+            line,
+            column: consumed[line_start..].chars().count() + 1,
+        }
+    }
+
+    pub fn error(&self, kind: ErrorKind) -> ParseError {
+        ParseError {
+            position: self.position(),
+            kind,
+        }
+    }
+
+    pub fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
+
+    /// Moves past the next character, if there is one.
+    pub fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.offset += c.len_utf8();
+        Some(c)
+    }
+
+    pub fn tag(&mut self, tag: &str) -> Result<()> {
+        if self.rest().starts_with(tag) {
+            self.offset += tag.len();
+            Ok(())
+        } else {
+            Err(self.error(ErrorKind::Expected(tag.to_string())))
+        }
+    }
+
+    pub fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.offset += rest.len() - rest.trim_start().len();
+    }
+
+    /// Takes everything up to (not including) `delim`, or the rest of the
+    /// input if `delim` never shows up.
+    pub fn until(&mut self, delim: &str) -> &'a str {
+        let rest = self.rest();
+        let taken = &rest[..rest.find(delim).unwrap_or(rest.len())];
+        self.offset += taken.len();
+        taken
+    }
+
+    /// An optionally signed decimal integer.
+    pub fn int<T: FromStr>(&mut self) -> Result<T> {
+        self.number(true, usize::MAX)
+    }
+
+    /// An unsigned number written with at most `max_digits` digits, for
+    /// fixed-width fields like the 1-3 digit operands of a `mul(X,Y)`.
+    pub fn digits<T: FromStr>(&mut self, max_digits: usize) -> Result<T> {
+        self.number(false, max_digits)
+    }
+
+    fn number<T: FromStr>(&mut self, signed: bool, max_digits: usize) -> Result<T> {
+        let rest = self.rest();
+        let sign = (signed && rest.starts_with(['-', '+'])) as usize;
+        let digits = rest[sign..].bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 || digits > max_digits {
+            return Err(self.error(ErrorKind::InvalidInteger));
+        }
+        match rest[..sign + digits].parse() {
+            Ok(n) => {
+                self.offset += sign + digits;
+                Ok(n)
+            }
+            // The digits are all valid, so either the value is out of range
+            // or it is negative and the type is unsigned, which "-0" tells.
+            Err(_) if rest.starts_with('-') && "-0".parse::<T>().is_err() => {
+                Err(self.error(ErrorKind::InvalidInteger))
+            }
+            Err(_) => Err(self.error(ErrorKind::IntegerOverflow)),
+        }
+    }
+
+    /// One or more `item`s separated by `sep`.
+    pub fn separated<T, F>(&mut self, sep: &str, mut item: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&mut Cursor<'a>) -> Result<T>,
+    {
+        let mut items = vec![item(self)?];
+        while self.rest().starts_with(sep) {
+            self.offset += sep.len();
+            items.push(item(self)?);
+        }
+        Ok(items)
+    }
+
+    /// Runs `parser`, rewinding to where we started if it fails.
+    pub fn attempt<T, F>(&mut self, parser: F) -> Result<T>
+    where
+        F: FnOnce(&mut Cursor<'a>) -> Result<T>,
+    {
+        let start = self.offset;
+        let result = parser(self);
+        if result.is_err() {
+            self.offset = start;
+        }
+        result
+    }
+
+    pub fn finish(&self) -> Result<()> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self.error(ErrorKind::TrailingInput(self.rest().to_string())))
+        }
+    }
+}
+
+/// Parses every line of `input` with `parser`, which must consume the whole
+/// line.
+pub fn lines<'a, T, F>(input: &'a str, parser: F) -> Result<Vec<T>>
+where
+    F: FnMut(&mut Cursor<'a>) -> Result<T>,
+{
+    lines_from(input, 1, parser)
+}
+
+/// `lines` for input that starts on `first_line` of a larger file.
+pub fn lines_from<'a, T, F>(input: &'a str, first_line: usize, mut parser: F) -> Result<Vec<T>>
+where
+    F: FnMut(&mut Cursor<'a>) -> Result<T>,
+{
+    input
+        .lines()
+        .enumerate()
+        .map(|(n, line)| {
+            let mut cursor = Cursor::at_line(line, first_line + n);
+            let parsed = parser(&mut cursor)?;
+            cursor.finish()?;
+            Ok(parsed)
+        })
+        .collect()
+}
+
+/// A line full of integers separated by `sep`, e.g. `ints("1,-2,3", ",")`.
+pub fn ints<T: FromStr>(line: &str, sep: &str) -> Result<Vec<T>> {
+    let mut cursor = Cursor::new(line);
+    let parsed = cursor.separated(sep, |c| c.int())?;
+    cursor.finish()?;
+    Ok(parsed)
+}
+
+/// A chunk of input between blank lines.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Block<'a> {
+    pub text: &'a str,
+    /// Line of the original input this block starts on.
+    pub line: usize,
+}
+
+impl<'a> Block<'a> {
+    pub fn cursor(&self) -> Cursor<'a> {
+        Cursor::at_line(self.text, self.line)
+    }
+
+    pub fn lines<T, F>(&self, parser: F) -> Result<Vec<T>>
+    where
+        F: FnMut(&mut Cursor<'a>) -> Result<T>,
+    {
+        lines_from(self.text, self.line, parser)
+    }
+}
+
+/// Splits `input` into blank-line-separated blocks.
+pub fn blocks(input: &str) -> Vec<Block<'_>> {
+    let mut blocks = Vec::new();
+    let mut start: Option<(usize, usize)> = None;
+    let mut offset = 0;
+    for (n, line) in input.split_inclusive('\n').enumerate() {
+        let blank = line.trim().is_empty();
+        match (blank, start) {
+            (false, None) => start = Some((offset, n + 1)),
+            (true, Some((from, line_no))) => {
+                blocks.push(Block {
+                    text: input[from..offset].trim_end_matches(['\n', '\r']),
+                    line: line_no,
+                });
+                start = None;
+            }
+            _ => {}
+        }
+        offset += line.len();
+    }
+    if let Some((from, line_no)) = start {
+        blocks.push(Block {
+            text: input[from..].trim_end_matches(['\n', '\r']),
+            line: line_no,
+        });
+    }
+    blocks
+}
+
+/// `key<sep>value` lines, both sides trimmed.
+pub fn key_values<'a>(input: &'a str, sep: &str) -> Result<Vec<(&'a str, &'a str)>> {
+    lines(input, |c| {
+        let key = c.until(sep);
+        c.tag(sep).map_err(|e| ParseError {
+            kind: ErrorKind::MissingSeparator(sep.to_string()),
+            ..e
+        })?;
+        let value = c.until("\n");
+        Ok((key.trim(), value.trim()))
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CharGrid {
+    pub width: usize,
+    pub height: usize,
+    pub cells: Vec<char>,
+}
+
+impl CharGrid {
+    pub fn get(&self, x: usize, y: usize) -> Option<char> {
+        if x < self.width && y < self.height {
+            Some(self.cells[y * self.width + x])
+        } else {
+            None
+        }
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[char]> {
+        self.cells.chunks(self.width.max(1))
+    }
+}
+
+/// A rectangular block of characters. Every row must be as wide as the
+/// first.
+pub fn char_grid(input: &str) -> Result<CharGrid> {
+    build_grid(input, None)
+}
+
+/// A block of characters where short rows are padded out with `fill`, for
+/// drawings whose trailing spaces an editor may have eaten.
+pub fn char_grid_padded(input: &str, fill: char) -> CharGrid {
+    build_grid(input, Some(fill)).expect("padded grids are never ragged")
+}
+
+fn build_grid(input: &str, fill: Option<char>) -> Result<CharGrid> {
+    let rows: Vec<Vec<char>> = input.lines().map(|l| l.chars().collect()).collect();
+    let width = match fill {
+        Some(_) => rows.iter().map(Vec::len).max().unwrap_or(0),
+        None => rows.first().map_or(0, Vec::len),
+    };
+    let mut cells = Vec::with_capacity(width * rows.len());
+    for (y, mut row) in rows.iter().cloned().enumerate() {
+        match fill {
+            Some(fill) => row.resize(width, fill),
+            None if row.len() != width => {
+                return Err(ParseError {
+                    position: Position {
+                        line: y + 1,
+                        column: row.len().min(width) + 1,
+                    },
+                    kind: ErrorKind::RaggedGrid {
+                        expected: width,
+                        found: row.len(),
+                    },
+                })
+            }
+            None => {}
+        }
+        cells.append(&mut row);
+    }
+    Ok(CharGrid {
+        width,
+        height: rows.len(),
+        cells,
+    })
+}
+
+#[test]
+fn test_cursor_ints() {
+    let mut c = Cursor::new("-12,+7,300");
+    assert_eq!(Ok(vec![-12, 7, 300]), c.separated(",", |c| c.int::<i32>()));
+    assert!(c.finish().is_ok());
+
+    let err = Cursor::new("abc").int::<i32>().unwrap_err();
+    assert_eq!(ErrorKind::InvalidInteger, err.kind);
+    let err = Cursor::new("300").int::<u8>().unwrap_err();
+    assert_eq!(ErrorKind::IntegerOverflow, err.kind);
+    let err = Cursor::new("-3").int::<u8>().unwrap_err();
+    assert_eq!(ErrorKind::InvalidInteger, err.kind);
+    let err = Cursor::new("-99999999999999999999")
+        .int::<i64>()
+        .unwrap_err();
+    assert_eq!(ErrorKind::IntegerOverflow, err.kind);
+    let err = Cursor::new("1234").digits::<u32>(3).unwrap_err();
+    assert_eq!(ErrorKind::InvalidInteger, err.kind);
+    let err = Cursor::new("-12").digits::<i32>(3).unwrap_err();
+    assert_eq!(ErrorKind::InvalidInteger, err.kind);
+}
+
+#[test]
+fn test_error_positions() {
+    let err = lines("1-2\n3-4\n5_6", |c| {
+        let a: u32 = c.int()?;
+        c.tag("-")?;
+        Ok((a, c.int::<u32>()?))
+    })
+    .unwrap_err();
+    assert_eq!(Position { line: 3, column: 2 }, err.position);
+    assert_eq!(ErrorKind::Expected("-".into()), err.kind);
+    assert_eq!("3:2: expected \"-\"", err.to_string());
+
+    let err = ints::<i64>("1 2 3x", " ").unwrap_err();
+    assert_eq!(ErrorKind::TrailingInput("x".into()), err.kind);
+    assert_eq!(6, err.position.column);
+}
+
+#[test]
+fn test_attempt_rewinds() {
+    let mut c = Cursor::new("mul(2,x)");
+    assert!(c
+        .attempt(|c| {
+            c.tag("mul(")?;
+            c.int::<i32>()?;
+            c.tag(",")?;
+            c.int::<i32>()
+        })
+        .is_err());
+    assert_eq!(0, c.offset());
+}
+
+#[test]
+fn test_blocks() {
+    let input = "a\nb\n\n\nc\n\nd\n";
+    let parsed = blocks(input);
+    assert_eq!(
+        vec![
+            Block {
+                text: "a\nb",
+                line: 1
+            },
+            Block { text: "c", line: 5 },
+            Block { text: "d", line: 7 },
+        ],
+        parsed
+    );
+    let err = parsed[1].cursor().int::<i32>().unwrap_err();
+    assert_eq!(Position { line: 5, column: 1 }, err.position);
+    let err = parsed[2].lines(|c| c.int::<i32>()).unwrap_err();
+    assert_eq!(Position { line: 7, column: 1 }, err.position);
+}
+
+#[test]
+fn test_key_values() {
+    assert_eq!(
+        Ok(vec![("Time", "7 15 30"), ("Distance", "9 40 200")]),
+        key_values("Time:  7 15 30\nDistance: 9 40 200", ":")
+    );
+    let err = key_values("a: 1\nb 2", ":").unwrap_err();
+    assert_eq!(ErrorKind::MissingSeparator(":".into()), err.kind);
+    assert_eq!(2, err.position.line);
+}
+
+#[test]
+fn test_char_grid() {
+    let grid = char_grid("ab\ncd").unwrap();
+    assert_eq!((2, 2), (grid.width, grid.height));
+    assert_eq!(Some('c'), grid.get(0, 1));
+    assert_eq!(None, grid.get(2, 0));
+    let err = char_grid("abc\nde").unwrap_err();
+    assert_eq!(
+        ErrorKind::RaggedGrid {
+            expected: 3,
+            found: 2
+        },
+        err.kind
+    );
+    let padded = char_grid_padded("abc\nd", '.');
+    assert_eq!(vec!['d', '.', '.'], padded.rows().nth(1).unwrap());
+}
@@ -0,0 +1,236 @@
+/*
+Linear recurrences over buckets, like lanternfish grouped by timer.
+
+A rule says where each bucket's population goes in one step. That makes a
+k×k transition matrix, and raising it to the Nth power by squaring advances
+any starting population N steps in O(k³ log N).
+*/
+
+use std::{error, fmt};
+
+use crate::math::{add_mod, mul_mod};
+
+/// A count no longer fits in a u128.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "population overflowed u128")
+    }
+}
+
+impl error::Error for Overflow {}
+
+/// A square matrix of counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Matrix {
+    size: usize,
+    cells: Vec<u128>,
+}
+
+impl Matrix {
+    pub fn zero(size: usize) -> Matrix {
+        Matrix {
+            size,
+            cells: vec![0; size * size],
+        }
+    }
+
+    pub fn identity(size: usize) -> Matrix {
+        let mut m = Matrix::zero(size);
+        for i in 0..size {
+            m.cells[i * size + i] = 1;
+        }
+        m
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> u128 {
+        self.cells[row * self.size + col]
+    }
+
+    fn combine<F>(&self, other: &Matrix, mut mul_add: F) -> Option<Matrix>
+    where
+        F: FnMut(u128, u128, u128) -> Option<u128>,
+    {
+        let mut product = Matrix::zero(self.size);
+        for row in 0..self.size {
+            for col in 0..self.size {
+                let mut sum = 0;
+                for k in 0..self.size {
+                    sum = mul_add(sum, self.get(row, k), other.get(k, col))?;
+                }
+                product.cells[row * self.size + col] = sum;
+            }
+        }
+        Some(product)
+    }
+
+    pub fn checked_mul(&self, other: &Matrix) -> Option<Matrix> {
+        self.combine(other, |sum, a, b| sum.checked_add(a.checked_mul(b)?))
+    }
+
+    pub fn mul_mod(&self, other: &Matrix, modulus: u128) -> Matrix {
+        self.combine(other, |sum, a, b| {
+            Some(add_mod(sum, mul_mod(a, b, modulus), modulus))
+        })
+        .unwrap()
+    }
+
+    fn pow_with<F>(&self, mut exp: u64, mut mul: F) -> Option<Matrix>
+    where
+        F: FnMut(&Matrix, &Matrix) -> Option<Matrix>,
+    {
+        let mut result = Matrix::identity(self.size);
+        let mut base = self.clone();
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = mul(&result, &base)?;
+            }
+            exp >>= 1;
+            // Don't square past the last bit; it can overflow for nothing.
+            if exp > 0 {
+                base = mul(&base, &base)?;
+            }
+        }
+        Some(result)
+    }
+
+    pub fn checked_pow(&self, exp: u64) -> Option<Matrix> {
+        self.pow_with(exp, Matrix::checked_mul)
+    }
+
+    pub fn pow_mod(&self, exp: u64, modulus: u128) -> Matrix {
+        let mut reduced = self.clone();
+        reduced.cells.iter_mut().for_each(|c| *c %= modulus);
+        reduced
+            .pow_with(exp, |a, b| Some(a.mul_mod(b, modulus)))
+            .unwrap()
+    }
+
+    /// The matrix times a column vector.
+    pub fn checked_apply(&self, state: &[u128]) -> Option<Vec<u128>> {
+        assert_eq!(
+            self.size,
+            state.len(),
+            "state has the wrong number of buckets"
+        );
+        (0..self.size)
+            .map(|row| {
+                state.iter().enumerate().try_fold(0u128, |sum, (col, n)| {
+                    sum.checked_add(self.get(row, col).checked_mul(*n)?)
+                })
+            })
+            .collect()
+    }
+
+    pub fn apply_mod(&self, state: &[u128], modulus: u128) -> Vec<u128> {
+        assert_eq!(
+            self.size,
+            state.len(),
+            "state has the wrong number of buckets"
+        );
+        (0..self.size)
+            .map(|row| {
+                state.iter().enumerate().fold(0, |sum, (col, n)| {
+                    add_mod(sum, mul_mod(self.get(row, col), *n, modulus), modulus)
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recurrence {
+    transition: Matrix,
+}
+
+impl Recurrence {
+    /// `rule(bucket)` lists the buckets that one member of `bucket` turns
+    /// into after a step. Listing a bucket twice counts it twice.
+    pub fn from_rule<F>(buckets: usize, rule: F) -> Recurrence
+    where
+        F: Fn(usize) -> Vec<usize>,
+    {
+        let mut transition = Matrix::zero(buckets);
+        for from in 0..buckets {
+            for to in rule(from) {
+                assert!(
+                    to < buckets,
+                    "bucket {} goes to missing bucket {}",
+                    from,
+                    to
+                );
+                transition.cells[to * buckets + from] += 1;
+            }
+        }
+        Recurrence { transition }
+    }
+
+    pub fn transition(&self) -> &Matrix {
+        &self.transition
+    }
+
+    /// The population in each bucket after `steps` steps.
+    pub fn advance(&self, state: &[u128], steps: u64) -> Result<Vec<u128>, Overflow> {
+        self.transition
+            .checked_pow(steps)
+            .and_then(|m| m.checked_apply(state))
+            .ok_or(Overflow)
+    }
+
+    /// `advance`, with every count reduced mod `modulus`. Never overflows,
+    /// so it can run for any number of steps.
+    pub fn advance_mod(&self, state: &[u128], steps: u64, modulus: u128) -> Vec<u128> {
+        assert!(modulus > 0, "modulus must be positive");
+        let state: Vec<u128> = state.iter().map(|n| n % modulus).collect();
+        self.transition
+            .pow_mod(steps, modulus)
+            .apply_mod(&state, modulus)
+    }
+}
+
+#[cfg(test)]
+fn lanternfish() -> (Recurrence, Vec<u128>) {
+    let fish = Recurrence::from_rule(9, |t| if t == 0 { vec![6, 8] } else { vec![t - 1] });
+    // 3,4,3,1,2
+    (fish, vec![0, 1, 1, 2, 1, 0, 0, 0, 0])
+}
+
+#[test]
+fn test_lanternfish() {
+    let (fish, start) = lanternfish();
+    let total = |steps| fish.advance(&start, steps).unwrap().iter().sum::<u128>();
+    assert_eq!(5, total(0));
+    assert_eq!(26, total(18));
+    assert_eq!(5934, total(80));
+    assert_eq!(26984457539, total(256));
+    assert_eq!(Err(Overflow), fish.advance(&start, 1_000_000_000));
+}
+
+#[test]
+fn test_advance_mod_matches_checked() {
+    let (fish, start) = lanternfish();
+    let m = 1_000_000_007;
+    let exact = fish.advance(&start, 500).unwrap();
+    let reduced = fish.advance_mod(&start, 500, m);
+    assert_eq!(exact.iter().map(|n| n % m).collect::<Vec<_>>(), reduced);
+    // A billion days, with the count taken mod 10^9+7.
+    let billion = fish.advance_mod(&start, 1_000_000_000, m);
+    assert_eq!(249911329, billion.iter().sum::<u128>() % m);
+    // Half a billion days twice over must land in the same place.
+    let half = fish.advance_mod(&start, 500_000_000, m);
+    assert_eq!(billion, fish.advance_mod(&half, 500_000_000, m));
+}
+
+#[test]
+fn test_fibonacci() {
+    // Bucket 0 is F(n+1), bucket 1 is F(n).
+    let fib = Recurrence::from_rule(2, |b| if b == 0 { vec![0, 1] } else { vec![0] });
+    assert_eq!(Ok(vec![6765 + 4181, 6765]), fib.advance(&[1, 0], 20));
+    assert!(fib.advance(&[1, 0], 200).is_err());
+}
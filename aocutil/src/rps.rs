@@ -0,0 +1,289 @@
+/*
+Rock Paper Scissors, and any other game shaped like it.
+
+A `Game` is a table: what each shape is called and scores, which shapes
+beat which, and what a loss, draw or win is worth. Classic RPS and
+Rock-Paper-Scissors-Lizard-Spock are built in. A strategy guide's second
+column can be read either as the shape to throw or as the outcome to aim
+for, and when nobody says what it means `best_cipher` finds the reading
+that scores the most.
+*/
+
+use crate::parse::{self, Cursor, ErrorKind, ParseError};
+
+/// Index of a shape in its game.
+pub type Shape = usize;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Lose,
+    Draw,
+    Win,
+}
+
+impl Outcome {
+    /// In guide order: X is lose, Y draw, Z win.
+    pub const ALL: [Outcome; 3] = [Outcome::Lose, Outcome::Draw, Outcome::Win];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Game {
+    names: Vec<&'static str>,
+    shape_scores: Vec<usize>,
+    /// `beats[a][b]` if shape `a` beats shape `b`.
+    beats: Vec<Vec<bool>>,
+    outcome_scores: [usize; 3],
+}
+
+impl Game {
+    /// A game where `shapes` are (name, score) and each pair in `beats` is
+    /// (winner, loser). Outcomes score 0, 3 and 6.
+    pub fn new(shapes: &[(&'static str, usize)], beats: &[(Shape, Shape)]) -> Game {
+        let n = shapes.len();
+        let mut table = vec![vec![false; n]; n];
+        for &(winner, loser) in beats {
+            assert!(winner != loser, "{} can't beat itself", shapes[winner].0);
+            assert!(
+                !table[loser][winner],
+                "{} and {} beat each other",
+                shapes[winner].0, shapes[loser].0
+            );
+            table[winner][loser] = true;
+        }
+        Game {
+            names: shapes.iter().map(|s| s.0).collect(),
+            shape_scores: shapes.iter().map(|s| s.1).collect(),
+            beats: table,
+            outcome_scores: [0, 3, 6],
+        }
+    }
+
+    pub fn with_outcome_scores(mut self, lose: usize, draw: usize, win: usize) -> Game {
+        self.outcome_scores = [lose, draw, win];
+        self
+    }
+
+    pub fn classic() -> Game {
+        Game::new(
+            &[("Rock", 1), ("Paper", 2), ("Scissors", 3)],
+            &[(0, 2), (1, 0), (2, 1)],
+        )
+    }
+
+    pub fn lizard_spock() -> Game {
+        let (rock, paper, scissors, lizard, spock) = (0, 1, 2, 3, 4);
+        Game::new(
+            &[
+                ("Rock", 1),
+                ("Paper", 2),
+                ("Scissors", 3),
+                ("Lizard", 4),
+                ("Spock", 5),
+            ],
+            &[
+                (scissors, paper),
+                (paper, rock),
+                (rock, lizard),
+                (lizard, spock),
+                (spock, scissors),
+                (scissors, lizard),
+                (lizard, paper),
+                (paper, spock),
+                (spock, rock),
+                (rock, scissors),
+            ],
+        )
+    }
+
+    pub fn shapes(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn name(&self, shape: Shape) -> &'static str {
+        self.names[shape]
+    }
+
+    /// How `mine` does against `theirs`. Shapes that neither beat nor lose
+    /// to each other draw.
+    pub fn outcome(&self, mine: Shape, theirs: Shape) -> Outcome {
+        if self.beats[mine][theirs] {
+            Outcome::Win
+        } else if self.beats[theirs][mine] {
+            Outcome::Lose
+        } else {
+            Outcome::Draw
+        }
+    }
+
+    pub fn score(&self, mine: Shape, theirs: Shape) -> usize {
+        self.shape_scores[mine] + self.outcome_scores[self.outcome(mine, theirs) as usize]
+    }
+
+    /// The best scoring shape that gets `outcome` against `theirs`, if any
+    /// does.
+    pub fn respond(&self, theirs: Shape, outcome: Outcome) -> Option<Shape> {
+        (0..self.shapes())
+            .filter(|&mine| self.outcome(mine, theirs) == outcome)
+            .max_by_key(|&mine| self.shape_scores[mine])
+    }
+}
+
+/// What the second column of a guide means.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Column {
+    /// The nth letter is the nth shape.
+    Shape,
+    /// The nth letter is the nth of `Outcome::ALL`.
+    Outcome,
+}
+
+/// One round: their shape and the index of the letter in the second column.
+pub type Round = (Shape, usize);
+
+/// A guide of lines like `A Y`, with the opponent's shapes lettered from
+/// `theirs` and the second column from `mine`.
+pub fn parse_guide(input: &str, theirs: &str, mine: &str) -> Result<Vec<Round>, ParseError> {
+    parse::lines(input, |c| {
+        let their_shape = letter(c, theirs)?;
+        c.tag(" ")?;
+        let column = letter(c, mine)?;
+        Ok((their_shape, column))
+    })
+}
+
+fn letter(c: &mut Cursor, letters: &str) -> Result<usize, ParseError> {
+    let found = c.peek().and_then(|l| letters.find(l));
+    match found {
+        Some(i) => {
+            c.bump();
+            Ok(i)
+        }
+        None => Err(c.error(ErrorKind::Expected(format!("one of {}", letters)))),
+    }
+}
+
+/// The total score of following the guide. Rounds asking for something
+/// the game can't do (a shape it doesn't have, an outcome no shape gets)
+/// are an error naming the (0-based) round.
+pub fn play(game: &Game, rounds: &[Round], column: Column) -> Result<usize, usize> {
+    rounds
+        .iter()
+        .enumerate()
+        .map(|(i, &(theirs, letter))| {
+            if theirs >= game.shapes() {
+                return Err(i);
+            }
+            let mine = match column {
+                Column::Shape => Some(letter).filter(|&s| s < game.shapes()),
+                Column::Outcome => Outcome::ALL
+                    .get(letter)
+                    .and_then(|&outcome| game.respond(theirs, outcome)),
+            };
+            mine.map(|mine| game.score(mine, theirs)).ok_or(i)
+        })
+        .sum()
+}
+
+/// Reads the second column as a cipher for shapes and tries every way of
+/// assigning them, returning the best (`cipher[letter]` is the shape to
+/// throw) with its total score. Every letter has to stand for one of the
+/// game's shapes, and a round using more letters or shapes than the game
+/// has is an error naming the (0-based) round, as in `play`.
+pub fn best_cipher(game: &Game, rounds: &[Round]) -> Result<(Vec<Shape>, usize), usize> {
+    let n = game.shapes();
+    // counts[letter][theirs]: how often each letter meets each shape.
+    let mut counts = vec![vec![0; n]; n];
+    for (i, &(theirs, letter)) in rounds.iter().enumerate() {
+        if theirs >= n || letter >= n {
+            return Err(i);
+        }
+        counts[letter][theirs] += 1;
+    }
+    let mut best = ((0..n).collect::<Vec<_>>(), 0);
+    let mut cipher: Vec<Shape> = (0..n).collect();
+    permute(&mut cipher, 0, &mut |cipher| {
+        let total = (0..n)
+            .map(|letter| {
+                (0..n)
+                    .map(|theirs| counts[letter][theirs] * game.score(cipher[letter], theirs))
+                    .sum::<usize>()
+            })
+            .sum();
+        if total > best.1 {
+            best = (cipher.to_vec(), total);
+        }
+    });
+    Ok(best)
+}
+
+fn permute<F: FnMut(&[usize])>(items: &mut [usize], k: usize, visit: &mut F) {
+    if k == items.len() {
+        visit(items);
+        return;
+    }
+    for i in k..items.len() {
+        items.swap(k, i);
+        permute(items, k + 1, visit);
+        items.swap(k, i);
+    }
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "A Y
+B X
+C Z";
+
+#[test]
+fn test_classic() {
+    let game = Game::classic();
+    let rounds = parse_guide(EXAMPLE, "ABC", "XYZ").unwrap();
+    assert_eq!(vec![(0, 1), (1, 0), (2, 2)], rounds);
+    assert_eq!(Outcome::Win, game.outcome(1, 0));
+    assert_eq!(8, game.score(1, 0));
+    assert_eq!(Ok(15), play(&game, &rounds, Column::Shape));
+    assert_eq!(Ok(12), play(&game, &rounds, Column::Outcome));
+    assert_eq!(Some(0), game.respond(2, Outcome::Win));
+    // X as Scissors, Y as Paper and Z as Rock wins every round.
+    assert_eq!(Ok((vec![2, 1, 0], 24)), best_cipher(&game, &rounds));
+}
+
+#[test]
+fn test_lizard_spock() {
+    let game = Game::lizard_spock();
+    for mine in 0..5 {
+        let wins = (0..5).filter(|&theirs| game.outcome(mine, theirs) == Outcome::Win);
+        assert_eq!(2, wins.count(), "{}", game.name(mine));
+    }
+    assert_eq!(Outcome::Win, game.outcome(3, 4));
+    // Rock is beaten by Paper and Spock; Spock scores more.
+    assert_eq!(Some(4), game.respond(0, Outcome::Win));
+    let rounds = parse_guide("A V\nE Z\nD X", "ABCDE", "VWXYZ").unwrap();
+    // Rock draws Rock, Spock draws Spock, Scissors decapitates Lizard.
+    assert_eq!(Ok(4 + 8 + 9), play(&game, &rounds, Column::Shape));
+    // There are only three outcomes, so Z means nothing.
+    assert_eq!(Err(1), play(&game, &rounds, Column::Outcome));
+}
+
+#[test]
+fn test_bad_guides() {
+    assert_eq!(
+        "2:3: expected \"one of XYZ\"",
+        parse_guide("A X\nB Q", "ABC", "XYZ")
+            .unwrap_err()
+            .to_string()
+    );
+    // Nothing beats Paper here.
+    let lopsided = Game::new(&[("Rock", 1), ("Paper", 2)], &[(1, 0)]).with_outcome_scores(0, 1, 2);
+    assert_eq!(Some(1), lopsided.respond(0, Outcome::Win));
+    assert_eq!(4, lopsided.score(1, 0));
+    assert_eq!(None, lopsided.respond(1, Outcome::Win));
+    assert_eq!(Err(0), play(&lopsided, &[(1, 2)], Column::Outcome));
+    // Four letters can't stand for three shapes, nor E for one of them.
+    let game = Game::classic();
+    let rounds = parse_guide("A W\nB Z", "ABC", "WXYZ").unwrap();
+    assert_eq!(Err(1), best_cipher(&game, &rounds));
+    let rounds = parse_guide("A X\nE X", "ABCDE", "XYZ").unwrap();
+    assert_eq!(Err(1), best_cipher(&game, &rounds));
+    assert_eq!(Err(1), play(&game, &rounds, Column::Shape));
+    assert_eq!(Err(1), play(&game, &rounds, Column::Outcome));
+}
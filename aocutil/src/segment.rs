@@ -0,0 +1,517 @@
+/*
+Line segments between integer points, like hydrothermal vent lines.
+
+A segment from `(x1, y1)` to `(x2, y2)` passes exactly through the points
+`from + k * step` for `k` in `0..=g`, where `g` is the gcd of the two
+deltas and `step` the delta divided by it. That covers horizontal,
+vertical and 45° lines, and any other slope too. `bresenham` instead walks
+the nearest cell for every step along the longer axis, for drawing.
+
+Overlaps can be counted two ways. `rasterize` marks every point of every
+segment on a grid, so it takes time in their total length.
+`count_overlaps` doesn't depend on length, only on the number of segments,
+though it tries every pair. It groups segments by the line they lie on and
+merges each line's runs as intervals, counting the stretch covered twice by
+length. Segments on different lines meet in at most one point, so each such
+pair adds at most one point on top.
+
+Coordinates are kept within `LIMIT` of the origin. Deltas then fit in
+`i64` with plenty to spare, and the cross products `intersection` takes in
+`i128` (a delta cubed, at worst) cannot overflow either.
+*/
+
+use std::{
+    collections::{HashMap, HashSet},
+    error, fmt,
+};
+
+use crate::{
+    grid::{Point, SparseGrid},
+    math::gcd,
+    parse::{self, Cursor, ErrorKind, ParseError},
+};
+
+/// How far from the origin, on either axis, a segment's ends can be.
+pub const LIMIT: i64 = 1 << 40;
+
+/// A segment end further than `LIMIT` from the origin.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct OutOfRange(pub Point);
+
+impl fmt::Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} is too far from the origin", self.0)
+    }
+}
+
+impl error::Error for OutOfRange {}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub from: Point,
+    pub to: Point,
+}
+
+/// How to turn a segment into grid points.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Stepping {
+    /// Only the points the segment passes through exactly.
+    Exact,
+    /// A connected run of cells as close to the segment as possible.
+    Bresenham,
+}
+
+fn cross(a: (i128, i128), b: (i128, i128)) -> i128 {
+    a.0 * b.1 - a.1 * b.0
+}
+
+fn wide(p: Point) -> (i128, i128) {
+    (p.0 as i128, p.1 as i128)
+}
+
+/// `to - from`, widened to take products of.
+fn gap(from: Point, to: Point) -> (i128, i128) {
+    (to.0 as i128 - from.0 as i128, to.1 as i128 - from.1 as i128)
+}
+
+impl Segment {
+    /// Panics if either end is out of range; see `try_new`.
+    pub fn new(from: Point, to: Point) -> Segment {
+        Segment::try_new(from, to).expect("segment end out of range")
+    }
+
+    pub fn try_new(from: Point, to: Point) -> Result<Segment, OutOfRange> {
+        for p in [from, to] {
+            if p.0.abs() > LIMIT || p.1.abs() > LIMIT {
+                return Err(OutOfRange(p));
+            }
+        }
+        Ok(Segment { from, to })
+    }
+
+    pub fn delta(&self) -> Point {
+        (self.to.0 - self.from.0, self.to.1 - self.from.1)
+    }
+
+    /// How many steps there are between the points the segment passes
+    /// through exactly; one less than the number of points.
+    pub fn steps(&self) -> i64 {
+        let (dx, dy) = self.delta();
+        gcd(dx, dy)
+    }
+
+    /// The smallest move from one exact point to the next, `(0, 0)` for a
+    /// segment that is a single point.
+    pub fn step(&self) -> Point {
+        let (dx, dy) = self.delta();
+        match self.steps() {
+            0 => (0, 0),
+            g => (dx / g, dy / g),
+        }
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        self.from.1 == self.to.1
+    }
+
+    pub fn is_vertical(&self) -> bool {
+        self.from.0 == self.to.0
+    }
+
+    pub fn is_axis_aligned(&self) -> bool {
+        self.is_horizontal() || self.is_vertical()
+    }
+
+    /// At exactly 45°.
+    pub fn is_diagonal(&self) -> bool {
+        let (dx, dy) = self.delta();
+        dx != 0 && dx.abs() == dy.abs()
+    }
+
+    /// Every point the segment passes through exactly, from `from` to `to`.
+    pub fn points(&self) -> impl Iterator<Item = Point> {
+        let (from, (sx, sy)) = (self.from, self.step());
+        (0..=self.steps()).map(move |k| (from.0 + k * sx, from.1 + k * sy))
+    }
+
+    pub fn bresenham(&self) -> Bresenham {
+        let (dx, dy) = self.delta();
+        Bresenham {
+            at: Some(self.from),
+            to: self.to,
+            dx: dx.abs(),
+            dy: -dy.abs(),
+            sx: dx.signum(),
+            sy: dy.signum(),
+            error: dx.abs() - dy.abs(),
+        }
+    }
+
+    pub fn rasterize(&self, stepping: Stepping) -> Box<dyn Iterator<Item = Point>> {
+        match stepping {
+            Stepping::Exact => Box::new(self.points()),
+            Stepping::Bresenham => Box::new(self.bresenham()),
+        }
+    }
+
+    /// Whether the segment passes exactly through `p`.
+    pub fn contains(&self, p: Point) -> bool {
+        let between = |a: i64, b: i64, x: i64| a.min(b) <= x && x <= a.max(b);
+        cross(wide(self.delta()), gap(self.from, p)) == 0
+            && between(self.from.0, self.to.0, p.0)
+            && between(self.from.1, self.to.1, p.1)
+    }
+
+    /// The exact points both segments pass through, as a segment of its
+    /// own: a single point where they cross, a run where they lie along
+    /// each other, `None` if they share no integer point.
+    pub fn intersection(&self, other: &Segment) -> Option<Segment> {
+        let d1 = wide(self.delta());
+        let d2 = wide(other.delta());
+        let denom = cross(d1, d2);
+        if denom != 0 {
+            // from + t * d1 = other.from + s * d2, with t and s in 0..=1.
+            let apart = gap(self.from, other.from);
+            let t = cross(apart, d2);
+            let s = cross(apart, d1);
+            let within = |n: i128| {
+                if denom > 0 {
+                    (0..=denom).contains(&n)
+                } else {
+                    (denom..=0).contains(&n)
+                }
+            };
+            let (x, y) = (t * d1.0, t * d1.1);
+            if !within(t) || !within(s) || x % denom != 0 || y % denom != 0 {
+                return None;
+            }
+            let p = (
+                self.from.0 + (x / denom) as i64,
+                self.from.1 + (y / denom) as i64,
+            );
+            return Some(Segment::new(p, p));
+        }
+        if self.steps() == 0 {
+            return other.contains(self.from).then_some(*self);
+        }
+        if other.steps() == 0 {
+            return self.contains(other.from).then_some(*other);
+        }
+        if cross(gap(self.from, other.from), d1) != 0 {
+            // Parallel, on different lines.
+            return None;
+        }
+        // On the same line, so both segments' ends are a whole number of
+        // steps along this one.
+        let step = self.step();
+        let along = |p: Point| match step.0 {
+            0 => (p.1 - self.from.1) / step.1,
+            sx => (p.0 - self.from.0) / sx,
+        };
+        let (a, b) = (along(other.from), along(other.to));
+        let lo = a.min(b).max(0);
+        let hi = a.max(b).min(self.steps());
+        let at = |k: i64| (self.from.0 + k * step.0, self.from.1 + k * step.1);
+        (lo <= hi).then(|| Segment::new(at(lo), at(hi)))
+    }
+}
+
+/// The cells of a segment by Bresenham's algorithm, from `from` to `to`.
+#[derive(Debug, Clone)]
+pub struct Bresenham {
+    at: Option<Point>,
+    to: Point,
+    dx: i64,
+    dy: i64,
+    sx: i64,
+    sy: i64,
+    error: i64,
+}
+
+impl Iterator for Bresenham {
+    type Item = Point;
+
+    fn next(&mut self) -> Option<Point> {
+        let p = self.at?;
+        if p == self.to {
+            self.at = None;
+            return Some(p);
+        }
+        let (mut x, mut y) = p;
+        let twice = 2 * self.error;
+        if twice >= self.dy {
+            self.error += self.dy;
+            x += self.sx;
+        }
+        if twice <= self.dx {
+            self.error += self.dx;
+            y += self.sy;
+        }
+        self.at = Some((x, y));
+        Some(p)
+    }
+}
+
+/// Lines like `0,9 -> 5,9`.
+pub fn parse_segments(input: &str) -> Result<Vec<Segment>, ParseError> {
+    parse::lines(input.trim_end(), |c| {
+        let from = point(c)?;
+        c.tag(" -> ")?;
+        let to = point(c)?;
+        Ok(Segment::new(from, to))
+    })
+}
+
+fn point(c: &mut Cursor) -> parse::Result<Point> {
+    let x = coordinate(c)?;
+    c.tag(",")?;
+    Ok((x, coordinate(c)?))
+}
+
+fn coordinate(c: &mut Cursor) -> parse::Result<i64> {
+    let position = c.position();
+    let n: i64 = c.int()?;
+    if n.abs() > LIMIT {
+        return Err(ParseError {
+            position,
+            kind: ErrorKind::IntegerOverflow,
+        });
+    }
+    Ok(n)
+}
+
+/// The segments that count: only horizontal and vertical ones unless
+/// `diagonals`, in which case every slope does.
+pub fn selected(segments: &[Segment], diagonals: bool) -> impl Iterator<Item = &Segment> {
+    segments
+        .iter()
+        .filter(move |s| diagonals || s.is_axis_aligned())
+}
+
+/// How many segments cover each point.
+pub fn rasterize(segments: &[Segment], diagonals: bool, stepping: Stepping) -> SparseGrid<u32> {
+    let mut grid = SparseGrid::new(0);
+    for segment in selected(segments, diagonals) {
+        for p in segment.rasterize(stepping) {
+            *grid.get_mut(p) += 1;
+        }
+    }
+    grid
+}
+
+/// A line through lattice points, as its smallest step pointing right (or
+/// straight up) and `cross(step, p)`, the same for every `p` on it.
+type Line = (Point, i128);
+
+/// How many steps along a line `p` is, counted from wherever the line
+/// passes nearest the origin.
+fn along(step: Point, p: Point) -> i128 {
+    let (s, p) = (wide(step), wide(p));
+    // Points one step apart differ by |step|² in their dot product.
+    (p.0 * s.0 + p.1 * s.1).div_euclid(s.0 * s.0 + s.1 * s.1)
+}
+
+/// Which line a segment lies on and how far along it each end is, in
+/// steps. `None` for a single point, which lies on every line through it.
+fn placement(segment: &Segment) -> Option<(Line, (i128, i128))> {
+    let step = match segment.step() {
+        (0, 0) => return None,
+        (sx, sy) if sx < 0 || (sx == 0 && sy < 0) => (-sx, -sy),
+        step => step,
+    };
+    let offset = cross(wide(step), wide(segment.from));
+    let (a, b) = (along(step, segment.from), along(step, segment.to));
+    Some(((step, offset), (a.min(b), a.max(b))))
+}
+
+/// The stretches covered by at least two of the runs, merged and in order.
+fn doubled(runs: &[(i128, i128)]) -> Vec<(i128, i128)> {
+    let mut events: Vec<(i128, i32)> = runs
+        .iter()
+        .flat_map(|&(lo, hi)| [(lo, 1), (hi + 1, -1)])
+        .collect();
+    events.sort_unstable();
+    let mut stretches: Vec<(i128, i128)> = Vec::new();
+    let mut depth = 0;
+    for (i, &(at, change)) in events.iter().enumerate() {
+        depth += change;
+        let next = match events.get(i + 1) {
+            Some(&(next, _)) if depth >= 2 && next > at => next,
+            _ => continue,
+        };
+        match stretches.last_mut() {
+            Some(last) if last.1 + 1 == at => last.1 = next - 1,
+            _ => stretches.push((at, next - 1)),
+        }
+    }
+    stretches
+}
+
+/// How many points at least two segments pass through exactly, without
+/// visiting the points along runs that overlap.
+pub fn count_overlaps(segments: &[Segment], diagonals: bool) -> usize {
+    let chosen: Vec<&Segment> = selected(segments, diagonals).collect();
+    let placed: Vec<Option<(Line, (i128, i128))>> = chosen.iter().map(|s| placement(s)).collect();
+    let mut lines: HashMap<Line, Vec<(i128, i128)>> = HashMap::new();
+    for &(line, run) in placed.iter().flatten() {
+        lines.entry(line).or_default().push(run);
+    }
+    let runs: HashMap<Line, Vec<(i128, i128)>> = lines
+        .into_iter()
+        .map(|(line, runs)| (line, doubled(&runs)))
+        .collect();
+    let mut total: i128 = runs.values().flatten().map(|(lo, hi)| hi - lo + 1).sum();
+    // Where segments on different lines meet, and the lines meeting there.
+    let mut crossings: HashMap<Point, HashSet<Line>> = HashMap::new();
+    for (i, a) in chosen.iter().enumerate() {
+        for (j, b) in chosen.iter().enumerate().skip(i + 1) {
+            let (pa, pb) = (placed[i].map(|p| p.0), placed[j].map(|p| p.0));
+            if pa.is_some() && pa == pb {
+                continue;
+            }
+            if let Some(meet) = a.intersection(b) {
+                crossings
+                    .entry(meet.from)
+                    .or_default()
+                    .extend(pa.into_iter().chain(pb));
+            }
+        }
+    }
+    // A crossing counts once, less however often a doubled run already
+    // counted it.
+    for (p, through) in crossings {
+        let counted = through
+            .iter()
+            .filter(|line| {
+                let k = along(line.0, p);
+                runs[*line].iter().any(|&(lo, hi)| lo <= k && k <= hi)
+            })
+            .count();
+        total += 1 - counted as i128;
+    }
+    total as usize
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2
+";
+
+#[cfg(test)]
+fn rasterized_overlaps(segments: &[Segment], diagonals: bool) -> usize {
+    let grid = rasterize(segments, diagonals, Stepping::Exact);
+    grid.iter().filter(|(_, &n)| n >= 2).count()
+}
+
+#[test]
+fn test_hydrothermal_vents() {
+    let vents = parse_segments(EXAMPLE).unwrap();
+    assert_eq!(10, vents.len());
+    assert_eq!(5, rasterized_overlaps(&vents, false));
+    assert_eq!(5, count_overlaps(&vents, false));
+    assert_eq!(12, rasterized_overlaps(&vents, true));
+    assert_eq!(12, count_overlaps(&vents, true));
+    let grid = rasterize(&vents, true, Stepping::Exact);
+    let picture = grid.render(|&n| match n {
+        0 => '.',
+        n => char::from_digit(n, 10).unwrap(),
+    });
+    assert!(picture.starts_with("1.1....11.\n.111...2..\n"));
+}
+
+#[test]
+fn test_any_slope() {
+    let steep = Segment::new((0, 0), (2, 6));
+    assert_eq!(
+        vec![(0, 0), (1, 3), (2, 6)],
+        steep.points().collect::<Vec<_>>()
+    );
+    assert!(steep.contains((1, 3)));
+    assert!(!steep.contains((1, 2)));
+    let bresenham: Vec<_> = steep.bresenham().collect();
+    assert_eq!(7, bresenham.len());
+    assert_eq!(Some(&(2, 6)), bresenham.last());
+    // Crossing between lattice points shares nothing.
+    let other = Segment::new((0, 1), (1, 0));
+    assert_eq!(None, Segment::new((0, 0), (1, 1)).intersection(&other));
+    // Collinear runs share the overlapping stretch.
+    let a = Segment::new((0, 0), (6, 4));
+    let b = Segment::new((9, 6), (3, 2));
+    assert_eq!(Some(Segment::new((3, 2), (6, 4))), a.intersection(&b));
+    assert_eq!(2, count_overlaps(&[a, b], true));
+    assert_eq!(0, count_overlaps(&[a, b], false));
+}
+
+#[test]
+fn test_far_apart() {
+    let (lo, hi) = (-LIMIT, LIMIT);
+    let long = Segment::try_new((lo, lo), (hi, hi)).unwrap();
+    assert_eq!(2 * LIMIT, long.steps());
+    assert!(long.is_diagonal());
+    let across = Segment::try_new((lo, hi), (hi, lo)).unwrap();
+    assert_eq!(
+        Some(Segment::new((0, 0), (0, 0))),
+        long.intersection(&across)
+    );
+    let along = Segment::try_new((hi - 3, hi - 3), (hi, hi)).unwrap();
+    assert_eq!(Some(along), long.intersection(&along));
+    // Far too many points to draw, but counted as runs.
+    let half = Segment::try_new((0, 0), (hi, hi)).unwrap();
+    let count = |segments: &[Segment]| count_overlaps(segments, true) as i64;
+    assert_eq!(LIMIT + 1, count(&[long, half, along]));
+    assert_eq!(LIMIT + 1, count(&[long, half, across]));
+    assert_eq!(
+        LIMIT + 2,
+        count(&[long, half, across, Segment::new((lo, hi), (lo, hi))])
+    );
+    assert_eq!(
+        Err(OutOfRange((hi + 1, 0))),
+        Segment::try_new((0, 0), (hi + 1, 0))
+    );
+    assert_eq!(
+        "1:8: integer out of range",
+        parse_segments("0,0 -> 1099511627777,0")
+            .unwrap_err()
+            .to_string()
+    );
+}
+
+#[cfg(test)]
+use proptest::prelude::*;
+
+#[cfg(test)]
+fn segment() -> impl Strategy<Value = Segment> {
+    ((-8i64..8, -8i64..8), (-8i64..8, -8i64..8)).prop_map(|(from, to)| Segment::new(from, to))
+}
+
+#[cfg(test)]
+proptest! {
+    #[test]
+    fn prop_analytic_matches_raster(segments in proptest::collection::vec(segment(), 0..12)) {
+        prop_assert_eq!(rasterized_overlaps(&segments, true), count_overlaps(&segments, true));
+        prop_assert_eq!(rasterized_overlaps(&segments, false), count_overlaps(&segments, false));
+    }
+
+    #[test]
+    fn prop_bresenham_joins_the_ends(s in segment()) {
+        let cells: Vec<Point> = s.bresenham().collect();
+        prop_assert_eq!(Some(&s.from), cells.first());
+        prop_assert_eq!(Some(&s.to), cells.last());
+        let (dx, dy) = s.delta();
+        prop_assert_eq!(dx.abs().max(dy.abs()) as usize + 1, cells.len());
+        for pair in cells.windows(2) {
+            prop_assert!((pair[0].0 - pair[1].0).abs() <= 1 && (pair[0].1 - pair[1].1).abs() <= 1);
+        }
+        if s.is_axis_aligned() || s.is_diagonal() {
+            prop_assert_eq!(s.points().collect::<Vec<_>>(), cells);
+        }
+    }
+}
@@ -0,0 +1,276 @@
+/*
+A small finite-domain constraint solver for assignment puzzles.
+
+Each variable takes one of up to 128 values, and its domain is the set of
+values still possible, kept as a bitset. Constraints prune domains until
+nothing changes, and when that isn't enough to settle everything the solver
+guesses a value for the variable with the fewest options left and
+backtracks if the guess leads to an empty domain.
+*/
+
+/// A set of values, bit `v` set if value `v` is in it.
+pub type Domain = u128;
+
+/// The most values a variable can range over.
+pub const MAX_VALUES: usize = 128;
+
+/// A domain holding exactly `values`.
+pub fn domain<I: IntoIterator<Item = usize>>(values: I) -> Domain {
+    values.into_iter().fold(0, |d, v| d | 1 << v)
+}
+
+/// The values in `d`, smallest first.
+pub fn values(d: Domain) -> impl Iterator<Item = usize> {
+    (0..MAX_VALUES).filter(move |v| d >> v & 1 == 1)
+}
+
+fn single(d: Domain) -> Option<usize> {
+    (d.count_ones() == 1).then(|| d.trailing_zeros() as usize)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Constraint {
+    /// No two of the variables take the same value.
+    AllDifferent(Vec<usize>),
+    /// The values the variables take, as a set, are exactly one of the
+    /// options. An option as big as the variable list forces them all to be
+    /// different.
+    ImageIn {
+        vars: Vec<usize>,
+        options: Vec<Domain>,
+    },
+}
+
+/// The domains had to be emptied, so there's no solution from here.
+struct Conflict;
+
+impl Constraint {
+    /// Narrows `domains`. Returns whether anything changed.
+    fn propagate(&self, domains: &mut [Domain]) -> Result<bool, Conflict> {
+        match self {
+            Constraint::AllDifferent(vars) => all_different(vars, domains),
+            Constraint::ImageIn { vars, options } => image_in(vars, options, domains),
+        }
+    }
+}
+
+fn all_different(vars: &[usize], domains: &mut [Domain]) -> Result<bool, Conflict> {
+    // Naked subsets: if k variables only have the same k values between
+    // them, nobody else can have those values. A settled variable is the
+    // k = 1 case.
+    let mut changed = false;
+    let mut progress = true;
+    while progress {
+        progress = false;
+        for &var in vars {
+            let set = domains[var];
+            let inside = vars.iter().filter(|&&v| domains[v] & !set == 0).count();
+            let size = set.count_ones() as usize;
+            if inside > size {
+                return Err(Conflict);
+            }
+            if inside < size {
+                continue;
+            }
+            for &other in vars {
+                let d = domains[other];
+                if d & !set != 0 && d & set != 0 {
+                    domains[other] = d & !set;
+                    progress = true;
+                }
+            }
+        }
+        changed |= progress;
+    }
+    // Pigeonhole: fewer values left than variables to give them to.
+    let available = vars.iter().fold(0, |u, &v| u | domains[v]);
+    if (available.count_ones() as usize) < vars.len() {
+        return Err(Conflict);
+    }
+    Ok(changed)
+}
+
+fn image_in(vars: &[usize], options: &[Domain], domains: &mut [Domain]) -> Result<bool, Conflict> {
+    // An option is still possible if every variable can land in it and,
+    // between them, they can still cover all of it.
+    let possible = options
+        .iter()
+        .filter(|&&option| {
+            let mut covered = 0;
+            for &var in vars {
+                let inside = domains[var] & option;
+                if inside == 0 {
+                    return false;
+                }
+                covered |= inside;
+            }
+            covered == option && option.count_ones() as usize <= vars.len()
+        })
+        .fold(0, |u, option| u | option);
+    if possible == 0 {
+        return Err(Conflict);
+    }
+    let mut changed = false;
+    for &var in vars {
+        if domains[var] & !possible != 0 {
+            domains[var] &= possible;
+            changed = true;
+        }
+    }
+    Ok(changed)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solver {
+    domains: Vec<Domain>,
+    constraints: Vec<Constraint>,
+}
+
+impl Solver {
+    /// `variables` variables, each of which can be any of `0..values`.
+    pub fn new(variables: usize, values: usize) -> Solver {
+        assert!(values <= MAX_VALUES, "at most {} values", MAX_VALUES);
+        Solver {
+            domains: vec![domain(0..values); variables],
+            constraints: Vec::new(),
+        }
+    }
+
+    pub fn domain(&self, var: usize) -> Domain {
+        self.domains[var]
+    }
+
+    /// Only lets `var` take the values in `allowed`.
+    pub fn restrict(&mut self, var: usize, allowed: Domain) -> &mut Solver {
+        self.domains[var] &= allowed;
+        self
+    }
+
+    pub fn all_different<I: IntoIterator<Item = usize>>(&mut self, vars: I) -> &mut Solver {
+        self.add(Constraint::AllDifferent(vars.into_iter().collect()))
+    }
+
+    pub fn image_in<I, J>(&mut self, vars: I, options: J) -> &mut Solver
+    where
+        I: IntoIterator<Item = usize>,
+        J: IntoIterator<Item = Domain>,
+    {
+        self.add(Constraint::ImageIn {
+            vars: vars.into_iter().collect(),
+            options: options.into_iter().collect(),
+        })
+    }
+
+    pub fn add(&mut self, constraint: Constraint) -> &mut Solver {
+        self.constraints.push(constraint);
+        self
+    }
+
+    /// Runs every constraint until none of them changes anything, leaving
+    /// the narrowed domains behind. Returns false if a domain emptied, in
+    /// which case there is no solution.
+    pub fn propagate(&mut self) -> bool {
+        propagate(&self.constraints, &mut self.domains).is_ok()
+    }
+
+    /// The first solution found, as the value of each variable.
+    pub fn solve(&self) -> Option<Vec<usize>> {
+        let mut found = None;
+        self.search(self.domains.clone(), &mut |solution| {
+            found = Some(solution);
+            false
+        });
+        found
+    }
+
+    /// Every solution.
+    pub fn solutions(&self) -> Vec<Vec<usize>> {
+        let mut found = Vec::new();
+        self.search(self.domains.clone(), &mut |solution| {
+            found.push(solution);
+            true
+        });
+        found
+    }
+
+    /// Depth-first search. `found` says whether to keep looking, and the
+    /// return value passes that back up.
+    fn search<F>(&self, mut domains: Vec<Domain>, found: &mut F) -> bool
+    where
+        F: FnMut(Vec<usize>) -> bool,
+    {
+        if propagate(&self.constraints, &mut domains).is_err() {
+            return true;
+        }
+        let guess = (0..domains.len())
+            .filter(|&var| domains[var].count_ones() > 1)
+            .min_by_key(|&var| domains[var].count_ones());
+        let Some(var) = guess else {
+            return match domains.iter().map(|&d| single(d)).collect() {
+                Some(solution) => found(solution),
+                None => true,
+            };
+        };
+        for value in values(domains[var]) {
+            let mut next = domains.clone();
+            next[var] = 1 << value;
+            if !self.search(next, found) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn propagate(constraints: &[Constraint], domains: &mut [Domain]) -> Result<(), Conflict> {
+    if domains.contains(&0) {
+        return Err(Conflict);
+    }
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for constraint in constraints {
+            changed |= constraint.propagate(domains)?;
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_seven_segment_wiring() {
+    // Wires a-g are the variables, real segments a-g the values.
+    let digits = [
+        "abcefg", "cf", "acdeg", "acdfg", "bcdf", "abdfg", "abdefg", "acf", "abcdefg", "abcdfg",
+    ];
+    let segments = |s: &str| s.bytes().map(|b| (b - b'a') as usize).collect::<Vec<_>>();
+    let mut solver = Solver::new(7, 7);
+    solver.all_different(0..7);
+    for signal in "acedgfb cdfbe gcdfa fbcad dab cefabd cdfgeb eafb cagedb ab".split(' ') {
+        let options = digits
+            .iter()
+            .filter(|d| d.len() == signal.len())
+            .map(|d| domain(segments(d)));
+        solver.image_in(segments(signal), options);
+    }
+    assert!(solver.propagate());
+    // "ab" is a 1 and "dab" a 7, so wire d has to be segment a.
+    assert_eq!(domain([0]), solver.domain(3));
+    let wiring = solver.solve().unwrap();
+    assert_eq!(segments("cfgabde"), wiring);
+    assert_eq!(1, solver.solutions().len());
+}
+
+#[test]
+fn test_latin_squares() {
+    let n = 4;
+    let mut solver = Solver::new(n * n, n);
+    for i in 0..n {
+        solver.all_different((0..n).map(|j| i * n + j));
+        solver.all_different((0..n).map(|j| j * n + i));
+    }
+    assert_eq!(576, solver.solutions().len());
+
+    solver.restrict(0, domain([0])).restrict(1, domain([0]));
+    assert!(!solver.propagate());
+    assert_eq!(None, solver.solve());
+}
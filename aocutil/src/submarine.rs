@@ -0,0 +1,260 @@
+/*
+Piloting the submarine through a planned course.
+
+A course is a list of `forward`, `down` and `up` commands. What they do
+depends on how the submarine steers: `Plain` steering moves straight down
+and up, `Aimed` steering tilts the nose and only changes depth when moving
+forward. Either way `Submarine::replay` runs the course and keeps every
+state it passes through, which is the trajectory to plot.
+*/
+
+use std::{
+    error, fmt,
+    io::{self, Write},
+};
+
+use crate::parse::{Cursor, ParseError, Position};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CourseError {
+    Parse(ParseError),
+    /// A line started with something other than a known command.
+    UnknownCommand {
+        position: Position,
+        command: String,
+    },
+}
+
+impl fmt::Display for CourseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CourseError::Parse(e) => write!(f, "{}", e),
+            CourseError::UnknownCommand { position, command } => {
+                write!(f, "{}: unknown command {:?}", position, command)
+            }
+        }
+    }
+}
+
+impl error::Error for CourseError {}
+
+impl From<ParseError> for CourseError {
+    fn from(e: ParseError) -> CourseError {
+        CourseError::Parse(e)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Command {
+    Forward(i64),
+    Down(i64),
+    Up(i64),
+}
+
+/// One command per line, like `forward 5`.
+pub fn parse_course(input: &str) -> Result<Vec<Command>, CourseError> {
+    input
+        .trim_end()
+        .lines()
+        .enumerate()
+        .map(|(n, line)| {
+            let mut c = Cursor::at_line(line, n + 1);
+            let parsed = command(&mut c)?;
+            c.finish()?;
+            Ok(parsed)
+        })
+        .collect()
+}
+
+fn command(c: &mut Cursor) -> Result<Command, CourseError> {
+    let position = c.position();
+    let name = c.until(" ");
+    let command: fn(i64) -> Command = match name {
+        "forward" => Command::Forward,
+        "down" => Command::Down,
+        "up" => Command::Up,
+        _ => {
+            return Err(CourseError::UnknownCommand {
+                position,
+                command: name.to_string(),
+            })
+        }
+    };
+    c.tag(" ")?;
+    Ok(command(c.int()?))
+}
+
+/// Where the submarine is. Depth grows downwards.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct State {
+    pub position: i64,
+    pub depth: i64,
+    pub aim: i64,
+}
+
+impl State {
+    /// Horizontal position times depth, the puzzle's answer.
+    pub fn product(&self) -> i64 {
+        self.position * self.depth
+    }
+}
+
+/// How a command changes the submarine's state.
+pub trait Steering {
+    fn steer(&self, state: State, command: Command) -> State;
+}
+
+/// `down` and `up` change depth directly.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Plain;
+
+impl Steering for Plain {
+    fn steer(&self, state: State, command: Command) -> State {
+        match command {
+            Command::Forward(n) => State {
+                position: state.position + n,
+                ..state
+            },
+            Command::Down(n) => State {
+                depth: state.depth + n,
+                ..state
+            },
+            Command::Up(n) => State {
+                depth: state.depth - n,
+                ..state
+            },
+        }
+    }
+}
+
+/// `down` and `up` change the aim, and moving forward goes `aim` deeper
+/// for every step.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Aimed;
+
+impl Steering for Aimed {
+    fn steer(&self, state: State, command: Command) -> State {
+        match command {
+            Command::Forward(n) => State {
+                position: state.position + n,
+                depth: state.depth + state.aim * n,
+                ..state
+            },
+            Command::Down(n) => State {
+                aim: state.aim + n,
+                ..state
+            },
+            Command::Up(n) => State {
+                aim: state.aim - n,
+                ..state
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Submarine<S> {
+    steering: S,
+    state: State,
+}
+
+impl<S: Steering> Submarine<S> {
+    /// A submarine at the surface, not moving.
+    pub fn new(steering: S) -> Submarine<S> {
+        Submarine {
+            steering,
+            state: State::default(),
+        }
+    }
+
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    pub fn execute(&mut self, command: Command) -> State {
+        self.state = self.steering.steer(self.state, command);
+        self.state
+    }
+
+    /// Runs the whole course and returns every state along the way, the
+    /// starting one first.
+    pub fn replay(&mut self, course: &[Command]) -> Vec<State> {
+        let mut trajectory = Vec::with_capacity(course.len() + 1);
+        trajectory.push(self.state);
+        for &command in course {
+            trajectory.push(self.execute(command));
+        }
+        trajectory
+    }
+}
+
+/// A trajectory as CSV, one row per state, for a spreadsheet or plotting
+/// tool.
+pub fn write_trajectory(out: &mut impl Write, trajectory: &[State]) -> io::Result<()> {
+    writeln!(out, "step,position,depth,aim")?;
+    for (step, s) in trajectory.iter().enumerate() {
+        writeln!(out, "{},{},{},{}", step, s.position, s.depth, s.aim)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+const EXAMPLE: &str = "forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2
+";
+
+#[test]
+fn test_planned_course() {
+    let course = parse_course(EXAMPLE).unwrap();
+    assert_eq!(6, course.len());
+    assert_eq!(Command::Up(3), course[3]);
+    let mut plain = Submarine::new(Plain);
+    let trajectory = plain.replay(&course);
+    assert_eq!(7, trajectory.len());
+    assert_eq!(State::default(), trajectory[0]);
+    assert_eq!(150, plain.state().product());
+    let mut aimed = Submarine::new(Aimed);
+    aimed.replay(&course);
+    assert_eq!(
+        State {
+            position: 15,
+            depth: 60,
+            aim: 10
+        },
+        aimed.state()
+    );
+    assert_eq!(900, aimed.state().product());
+}
+
+#[test]
+fn test_trajectory_csv() {
+    let course = parse_course("forward 2\ndown 1\nforward 3").unwrap();
+    let mut out = Vec::new();
+    write_trajectory(&mut out, &Submarine::new(Aimed).replay(&course)).unwrap();
+    assert_eq!(
+        "step,position,depth,aim\n0,0,0,0\n1,2,0,0\n2,2,0,1\n3,5,3,1\n",
+        String::from_utf8(out).unwrap()
+    );
+}
+
+#[test]
+fn test_bad_courses() {
+    assert_eq!(
+        "2:1: unknown command \"backward\"",
+        parse_course("forward 1\nbackward 2")
+            .unwrap_err()
+            .to_string()
+    );
+    assert_eq!(
+        "1:6: expected an integer",
+        parse_course("down x").unwrap_err().to_string()
+    );
+    assert_eq!(
+        "1:3: expected \" \"",
+        parse_course("up").unwrap_err().to_string()
+    );
+}
@@ -0,0 +1,453 @@
+/*
+Terminal rendering for grids and simulations.
+
+A `Frame` is one picture: a box of glyphs, some of them coloured, with an
+optional caption. Build one from a `SparseGrid`, a set of points or a
+closure, highlight whatever matters (visible trees, tail positions, vent
+overlaps), and print it. A `Player` steps through a recorded run of frames
+with play/pause, single stepping and a speed control.
+*/
+
+use std::{
+    fmt,
+    io::{self, BufRead, Write},
+    str::FromStr,
+    sync::mpsc::{self, Receiver, RecvTimeoutError},
+    thread,
+    time::Duration,
+};
+
+use crate::grid::{Bounds, Point, SparseGrid};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Colour {
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    Grey,
+}
+
+impl Colour {
+    fn ansi(self) -> u8 {
+        match self {
+            Colour::Red => 31,
+            Colour::Green => 32,
+            Colour::Yellow => 33,
+            Colour::Blue => 34,
+            Colour::Magenta => 35,
+            Colour::Cyan => 36,
+            Colour::White => 97,
+            Colour::Grey => 90,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub glyph: char,
+    pub colour: Option<Colour>,
+}
+
+impl Cell {
+    pub fn plain(glyph: char) -> Cell {
+        Cell {
+            glyph,
+            colour: None,
+        }
+    }
+
+    pub fn coloured(glyph: char, colour: Colour) -> Cell {
+        Cell {
+            glyph,
+            colour: Some(colour),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    bounds: Bounds,
+    cells: Vec<Cell>,
+    caption: Option<String>,
+}
+
+impl Frame {
+    /// Calls `cell` for every point inside `bounds`.
+    pub fn from_fn<F>(bounds: Bounds, mut cell: F) -> Frame
+    where
+        F: FnMut(Point) -> Cell,
+    {
+        let area = bounds
+            .width()
+            .checked_mul(bounds.height())
+            .and_then(|n| usize::try_from(n).ok())
+            .expect("frame too large to draw");
+        let mut cells = Vec::with_capacity(area);
+        for y in bounds.min_y..=bounds.max_y {
+            for x in bounds.min_x..=bounds.max_x {
+                cells.push(cell((x, y)));
+            }
+        }
+        Frame {
+            bounds,
+            cells,
+            caption: None,
+        }
+    }
+
+    /// The grid's bounding box, with unset cells drawn from the default.
+    pub fn from_grid<T, F>(grid: &SparseGrid<T>, mut cell: F) -> Frame
+    where
+        F: FnMut(&T) -> Cell,
+    {
+        let bounds = grid.bounds().unwrap_or(Bounds::around((0, 0)));
+        Frame::from_fn(bounds, |p| cell(grid.get(p)))
+    }
+
+    /// `glyph` at each point and `.` everywhere else in their bounding box.
+    pub fn from_points<I>(points: I, glyph: char) -> Frame
+    where
+        I: IntoIterator<Item = Point>,
+    {
+        let grid: SparseGrid<bool> = points.into_iter().map(|p| (p, true)).collect();
+        Frame::from_grid(&grid, |set| Cell::plain(if *set { glyph } else { '.' }))
+    }
+
+    pub fn bounds(&self) -> Bounds {
+        self.bounds
+    }
+
+    fn index(&self, p: Point) -> Option<usize> {
+        self.bounds.contains(p).then(|| {
+            let row = (p.1 - self.bounds.min_y) as usize;
+            let col = (p.0 - self.bounds.min_x) as usize;
+            row * self.bounds.width() as usize + col
+        })
+    }
+
+    pub fn get(&self, p: Point) -> Option<Cell> {
+        self.index(p).map(|i| self.cells[i])
+    }
+
+    /// Replaces the cell at `p`. Points outside the frame are ignored.
+    pub fn set(&mut self, p: Point, cell: Cell) {
+        if let Some(i) = self.index(p) {
+            self.cells[i] = cell;
+        }
+    }
+
+    /// Colours every one of `points` that falls inside the frame.
+    pub fn highlight<I>(&mut self, points: I, colour: Colour) -> &mut Frame
+    where
+        I: IntoIterator<Item = Point>,
+    {
+        for p in points {
+            if let Some(i) = self.index(p) {
+                self.cells[i].colour = Some(colour);
+            }
+        }
+        self
+    }
+
+    pub fn with_caption(mut self, caption: impl Into<String>) -> Frame {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    /// The frame as lines of text, with ANSI colour codes if `colour`.
+    pub fn render(&self, colour: bool) -> String {
+        let mut out = String::new();
+        if let Some(caption) = &self.caption {
+            out.push_str(caption);
+            out.push('\n');
+        }
+        for row in self.cells.chunks(self.bounds.width() as usize) {
+            let mut current = None;
+            for cell in row {
+                if colour && cell.colour != current {
+                    match cell.colour {
+                        Some(c) => out.push_str(&format!("\x1b[{}m", c.ansi())),
+                        None => out.push_str("\x1b[0m"),
+                    }
+                    current = cell.colour;
+                }
+                out.push(cell.glyph);
+            }
+            if current.is_some() {
+                out.push_str("\x1b[0m");
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.render(false))
+    }
+}
+
+/// Playback controls, typed one per line while a `Player` runs.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Command {
+    /// Pause and show the next frame.
+    Step,
+    /// Pause and show the previous frame.
+    Back,
+    /// Toggle between playing and paused.
+    Toggle,
+    Faster,
+    Slower,
+    Quit,
+}
+
+impl FromStr for Command {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Command, String> {
+        // Trimming would turn a line of spaces into a step.
+        if !s.is_empty() && s.trim_matches(' ').is_empty() {
+            return Ok(Command::Toggle);
+        }
+        match s.trim() {
+            "" | "n" => Ok(Command::Step),
+            "b" => Ok(Command::Back),
+            "p" => Ok(Command::Toggle),
+            "+" | "f" => Ok(Command::Faster),
+            "-" | "s" => Ok(Command::Slower),
+            "q" => Ok(Command::Quit),
+            other => Err(format!("unknown command {:?}", other)),
+        }
+    }
+}
+
+const HELP: &str = "[enter] step  [b] back  [p/space] play/pause  [+/-] speed  [q] quit";
+
+pub struct Player {
+    frames: Vec<Frame>,
+    position: usize,
+    delay: Duration,
+    playing: bool,
+    colour: bool,
+}
+
+impl Player {
+    /// Starts paused on the first frame, with colour on.
+    pub fn new(frames: Vec<Frame>) -> Player {
+        Player {
+            frames,
+            position: 0,
+            delay: Duration::from_millis(200),
+            playing: false,
+            colour: true,
+        }
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Player {
+        self.delay = delay;
+        self
+    }
+
+    pub fn colour(mut self, colour: bool) -> Player {
+        self.colour = colour;
+        self
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+
+    pub fn current_delay(&self) -> Duration {
+        self.delay
+    }
+
+    /// Carries out one command. Returns false once playback should stop.
+    pub fn apply(&mut self, command: Command) -> bool {
+        match command {
+            Command::Step => {
+                self.playing = false;
+                self.advance();
+            }
+            Command::Back => {
+                self.playing = false;
+                self.position = self.position.saturating_sub(1);
+            }
+            Command::Toggle => self.playing = !self.playing,
+            Command::Faster => self.delay = (self.delay / 2).max(Duration::from_millis(10)),
+            Command::Slower => self.delay = (self.delay * 2).min(Duration::from_secs(5)),
+            Command::Quit => return false,
+        }
+        true
+    }
+
+    fn advance(&mut self) {
+        if self.position + 1 < self.frames.len() {
+            self.position += 1;
+        } else {
+            self.playing = false;
+        }
+    }
+
+    fn draw(&self, out: &mut impl Write) -> io::Result<()> {
+        let Some(frame) = self.frames.get(self.position) else {
+            return Ok(());
+        };
+        if self.colour {
+            // Clear the screen and home the cursor.
+            write!(out, "\x1b[2J\x1b[H")?;
+        }
+        write!(out, "{}", frame.render(self.colour))?;
+        writeln!(
+            out,
+            "frame {}/{} {} {}ms  {}",
+            self.position + 1,
+            self.frames.len(),
+            if self.playing { "playing" } else { "paused" },
+            self.delay.as_millis(),
+            HELP
+        )?;
+        out.flush()
+    }
+
+    /// Draws frames to `out`, taking commands from `commands`. While
+    /// playing, a frame goes by every `delay` unless a command arrives
+    /// first. Stops on `Quit` or when `commands` hangs up.
+    pub fn run(&mut self, out: &mut impl Write, commands: Receiver<Command>) -> io::Result<()> {
+        self.draw(out)?;
+        loop {
+            let command = if self.playing {
+                match commands.recv_timeout(self.delay) {
+                    Ok(command) => Some(command),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return Ok(()),
+                }
+            } else {
+                match commands.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => return Ok(()),
+                }
+            };
+            match command {
+                Some(command) if !self.apply(command) => return Ok(()),
+                Some(_) => {}
+                None => self.advance(),
+            }
+            self.draw(out)?;
+        }
+    }
+
+    /// `run` on the terminal, reading commands from stdin.
+    pub fn play(&mut self) -> io::Result<()> {
+        let (send, commands) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lock().lines() {
+                let Ok(line) = line else { break };
+                match line.parse() {
+                    Ok(command) => {
+                        if send.send(command).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => eprintln!("{}; {}", e, HELP),
+                }
+            }
+        });
+        self.run(&mut io::stdout().lock(), commands)
+    }
+}
+
+#[test]
+fn test_frame_from_points() {
+    let mut frame = Frame::from_points([(0, 0), (2, 1), (-1, 1)], '#');
+    assert_eq!(".#..\n#..#\n", frame.to_string());
+    frame.highlight([(2, 1), (50, 50)], Colour::Red);
+    assert_eq!(Some(Cell::coloured('#', Colour::Red)), frame.get((2, 1)));
+    assert_eq!(".#..\n#..\x1b[31m#\x1b[0m\n", frame.render(true));
+    let captioned = frame.with_caption("== R 4 ==");
+    assert_eq!("== R 4 ==\n.#..\n#..#\n", captioned.to_string());
+}
+
+#[test]
+fn test_frame_from_grid() {
+    let mut grid = SparseGrid::new(0);
+    grid.set((1, 1), 3);
+    grid.set((3, 2), 1);
+    let frame = Frame::from_grid(&grid, |n| match n {
+        0 => Cell::plain('.'),
+        n => Cell::coloured(char::from_digit(*n, 10).unwrap(), Colour::Yellow),
+    });
+    assert_eq!("3..\n..1\n", frame.to_string());
+}
+
+#[test]
+fn test_player_commands() {
+    let frames = (0..3).map(|n| Frame::from_points([(n, 0)], '@')).collect();
+    let mut player = Player::new(frames).colour(false);
+    let (send, commands) = mpsc::channel();
+    for command in "\n\nb\n+\n-\n-\nq\nn".lines() {
+        send.send(command.parse().unwrap()).unwrap();
+    }
+    let mut out = Vec::new();
+    player.run(&mut out, commands).unwrap();
+    assert_eq!(1, player.position());
+    assert_eq!(Duration::from_millis(400), player.current_delay());
+    let out = String::from_utf8(out).unwrap();
+    assert_eq!(7, out.matches("paused").count());
+    assert!(out.starts_with("@\nframe 1/3 paused 200ms"));
+    assert_eq!(Err("unknown command \"x\"".into()), "x".parse::<Command>());
+    assert_eq!(Ok(Command::Toggle), " ".parse());
+    assert_eq!(Ok(Command::Toggle), " p\r".parse());
+    assert_eq!(Ok(Command::Step), "\r".parse());
+}
+
+/// Collects what the player draws, hanging up on it once `last` shows.
+#[cfg(test)]
+struct HangUp {
+    out: Vec<u8>,
+    last: &'static str,
+    send: Option<mpsc::Sender<Command>>,
+}
+
+#[cfg(test)]
+impl Write for HangUp {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.out.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if String::from_utf8_lossy(&self.out).contains(self.last) {
+            self.send = None;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_player_plays_to_the_end() {
+    let frames = (0..5).map(|n| Frame::from_points([(n, 0)], '@')).collect();
+    let mut player = Player::new(frames)
+        .colour(false)
+        .delay(Duration::from_millis(1));
+    let (send, commands) = mpsc::channel();
+    send.send(Command::Toggle).unwrap();
+    // Hang up once the player has stopped by itself on the last frame.
+    let mut out = HangUp {
+        out: Vec::new(),
+        last: "frame 5/5 paused",
+        send: Some(send),
+    };
+    player.run(&mut out, commands).unwrap();
+    assert_eq!(4, player.position());
+    let out = String::from_utf8(out.out).unwrap();
+    assert!(out.contains("frame 5/5 playing"));
+    assert!(!player.is_playing());
+}
@@ -0,0 +1,336 @@
+/*
+Sliding windows over a stream of items.
+
+`Sliding` adds adaptors to any iterator: the sum of every window of a given
+width, each item paired with the one some fixed offset after it, and how
+often a window's sum goes up from the one before. All of them keep only the
+current window in memory.
+
+The other windows here know whether everything in them is different. Each
+keeps a count of every item it holds and how many items are held more than
+once, so pushing one item in (and the oldest one out) costs O(1) and
+finding the first run of `width` distinct items costs O(n) whatever the
+width. `ByteWindow` counts in a flat table for bytes and can be fed
+straight from a reader; `DistinctWindow` does the same for anything
+hashable.
+*/
+
+use std::{
+    collections::{HashMap, VecDeque},
+    hash::Hash,
+    io::{self, BufRead, BufReader, Read},
+    ops::{Add, Sub},
+};
+
+pub trait Sliding: Iterator + Sized {
+    /// The sum of each full window of `width` items, in order.
+    fn window_sums(self, width: usize) -> WindowSums<Self>
+    where
+        Self::Item: Copy + Default + Add<Output = Self::Item> + Sub<Output = Self::Item>,
+    {
+        assert!(width > 0, "windows must hold at least one item");
+        WindowSums {
+            iter: self,
+            window: VecDeque::with_capacity(width + 1),
+            width,
+            sum: Default::default(),
+        }
+    }
+
+    /// Every item alongside the one `offset` places after it. Stops when
+    /// there is no item that far ahead.
+    fn offset_pairs(self, offset: usize) -> OffsetPairs<Self>
+    where
+        Self::Item: Clone,
+    {
+        OffsetPairs {
+            iter: self,
+            buffer: VecDeque::with_capacity(offset + 1),
+            offset,
+        }
+    }
+
+    /// How many windows of `width` items have a bigger sum than the window
+    /// one place before. Neighbouring windows share all but their ends, so
+    /// this only compares items `width` apart and never adds anything up.
+    fn count_increases(self, width: usize) -> usize
+    where
+        Self::Item: Clone + PartialOrd,
+    {
+        self.offset_pairs(width)
+            .filter(|(before, after)| after > before)
+            .count()
+    }
+}
+
+impl<I: Iterator> Sliding for I {}
+
+#[derive(Debug, Clone)]
+pub struct WindowSums<I: Iterator> {
+    iter: I,
+    window: VecDeque<I::Item>,
+    width: usize,
+    sum: I::Item,
+}
+
+impl<I, T> Iterator for WindowSums<I>
+where
+    I: Iterator<Item = T>,
+    T: Copy + Add<Output = T> + Sub<Output = T>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        loop {
+            let item = self.iter.next()?;
+            self.window.push_back(item);
+            self.sum = self.sum + item;
+            if self.window.len() > self.width {
+                let oldest = self.window.pop_front().expect("window is not empty");
+                self.sum = self.sum - oldest;
+            }
+            if self.window.len() == self.width {
+                return Some(self.sum);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct OffsetPairs<I: Iterator> {
+    iter: I,
+    buffer: VecDeque<I::Item>,
+    offset: usize,
+}
+
+impl<I> Iterator for OffsetPairs<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = (I::Item, I::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let item = self.iter.next()?;
+            self.buffer.push_back(item.clone());
+            if self.buffer.len() > self.offset {
+                let before = self.buffer.pop_front().expect("buffer is not empty");
+                return Some((before, item));
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DistinctWindow<T> {
+    width: usize,
+    items: VecDeque<T>,
+    counts: HashMap<T, usize>,
+    repeated: usize,
+    seen: usize,
+}
+
+impl<T: Eq + Hash + Clone> DistinctWindow<T> {
+    pub fn new(width: usize) -> DistinctWindow<T> {
+        DistinctWindow {
+            width,
+            items: VecDeque::with_capacity(width + 1),
+            counts: HashMap::with_capacity(width + 1),
+            repeated: 0,
+            seen: 0,
+        }
+    }
+
+    /// Slides the window along by one. Returns whether it is now full and
+    /// holds no item twice.
+    pub fn push(&mut self, item: T) -> bool {
+        self.seen += 1;
+        let count = self.counts.entry(item.clone()).or_insert(0);
+        *count += 1;
+        if *count == 2 {
+            self.repeated += 1;
+        }
+        self.items.push_back(item);
+        if self.items.len() > self.width {
+            let oldest = self.items.pop_front().expect("window is not empty");
+            let count = self.counts.get_mut(&oldest).expect("counted on the way in");
+            *count -= 1;
+            match *count {
+                0 => {
+                    self.counts.remove(&oldest);
+                }
+                1 => self.repeated -= 1,
+                _ => {}
+            }
+        }
+        self.is_distinct()
+    }
+
+    pub fn is_distinct(&self) -> bool {
+        self.items.len() == self.width && self.repeated == 0
+    }
+
+    /// How many items have been pushed in total.
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ByteWindow {
+    ring: Vec<u8>,
+    counts: [u32; 256],
+    repeated: usize,
+    seen: usize,
+}
+
+impl ByteWindow {
+    pub fn new(width: usize) -> ByteWindow {
+        ByteWindow {
+            ring: vec![0; width],
+            counts: [0; 256],
+            repeated: 0,
+            seen: 0,
+        }
+    }
+
+    /// `DistinctWindow::push` for a byte.
+    pub fn push(&mut self, byte: u8) -> bool {
+        let width = self.ring.len();
+        if width > 0 {
+            let slot = self.seen % width;
+            if self.seen >= width {
+                let oldest = self.ring[slot] as usize;
+                self.counts[oldest] -= 1;
+                if self.counts[oldest] == 1 {
+                    self.repeated -= 1;
+                }
+            }
+            self.ring[slot] = byte;
+            self.counts[byte as usize] += 1;
+            if self.counts[byte as usize] == 2 {
+                self.repeated += 1;
+            }
+        }
+        self.seen += 1;
+        self.is_distinct()
+    }
+
+    pub fn is_distinct(&self) -> bool {
+        self.seen >= self.ring.len() && self.repeated == 0
+    }
+
+    pub fn seen(&self) -> usize {
+        self.seen
+    }
+}
+
+/// How many items have to be read before the last `width` of them are all
+/// different, or `None` if that never happens.
+pub fn first_distinct<T, I>(items: I, width: usize) -> Option<usize>
+where
+    T: Eq + Hash + Clone,
+    I: IntoIterator<Item = T>,
+{
+    if width == 0 {
+        return Some(0);
+    }
+    let mut window = DistinctWindow::new(width);
+    items
+        .into_iter()
+        .find(|item| window.push(item.clone()))
+        .map(|_| window.seen())
+}
+
+/// `first_distinct` over bytes.
+pub fn first_distinct_bytes(bytes: &[u8], width: usize) -> Option<usize> {
+    if width == 0 {
+        return Some(0);
+    }
+    let mut window = ByteWindow::new(width);
+    bytes.iter().position(|&b| window.push(b)).map(|at| at + 1)
+}
+
+/// `first_distinct_bytes` reading from `reader` a buffer at a time, so the
+/// whole signal never has to be in memory. Stops reading once it's found.
+pub fn first_distinct_in<R: Read>(reader: R, width: usize) -> io::Result<Option<usize>> {
+    if width == 0 {
+        return Ok(Some(0));
+    }
+    let mut reader = BufReader::new(reader);
+    let mut window = ByteWindow::new(width);
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        if buf.iter().any(|&b| window.push(b)) {
+            return Ok(Some(window.seen()));
+        }
+        let len = buf.len();
+        reader.consume(len);
+    }
+}
+
+#[test]
+fn test_first_distinct() {
+    let signal = "mjqjpqmgbljsphdztnvjfqwrcgsmlb";
+    assert_eq!(Some(7), first_distinct(signal.chars(), 4));
+    assert_eq!(Some(19), first_distinct(signal.chars(), 14));
+    assert_eq!(Some(7), first_distinct_bytes(signal.as_bytes(), 4));
+    assert_eq!(Some(19), first_distinct_bytes(signal.as_bytes(), 14));
+    assert_eq!(Some(19), first_distinct_in(signal.as_bytes(), 14).unwrap());
+    assert_eq!(None, first_distinct_bytes(b"abcabc", 4));
+    assert_eq!(None, first_distinct_in(&b"aaaa"[..], 2).unwrap());
+    assert_eq!(Some(0), first_distinct_bytes(b"", 0));
+    assert_eq!(Some(1), first_distinct_bytes(b"a", 1));
+}
+
+#[test]
+fn test_non_char_items() {
+    let readings = [3, 1, 3, 3, 7, 1, 2, 7, 4];
+    assert_eq!(Some(6), first_distinct(readings, 3));
+    assert_eq!(Some(7), first_distinct(readings, 4));
+    let mut window = DistinctWindow::new(2);
+    assert!(!window.push("a"));
+    assert!(window.push("b"));
+    assert!(!window.push("b"));
+    assert!(window.push("a"));
+}
+
+#[test]
+fn test_streams_across_buffers() {
+    // A long run of repeats, with the marker well past the reader's
+    // internal buffer size.
+    let mut signal = "ab".repeat(20_000).into_bytes();
+    signal.extend_from_slice(b"abcdefghijklmn");
+    let expected = first_distinct_bytes(&signal, 14);
+    assert_eq!(Some(40_014), expected);
+    assert_eq!(expected, first_distinct_in(&signal[..], 14).unwrap());
+    assert_eq!(expected, first_distinct(signal.iter(), 14));
+}
+
+#[cfg(test)]
+const SONAR: [i32; 10] = [199, 200, 208, 210, 200, 207, 240, 269, 260, 263];
+
+#[test]
+fn test_sonar_sweep() {
+    assert_eq!(7, SONAR.iter().count_increases(1));
+    assert_eq!(5, SONAR.iter().count_increases(3));
+    let sums: Vec<i32> = SONAR.iter().copied().window_sums(3).collect();
+    assert_eq!(vec![607, 618, 618, 617, 647, 716, 769, 792], sums);
+    // Counting increases over summed windows gives the same answer.
+    assert_eq!(5, sums.iter().count_increases(1));
+}
+
+#[test]
+fn test_offsets() {
+    let pairs: Vec<_> = (1..=5).offset_pairs(2).collect();
+    assert_eq!(vec![(1, 3), (2, 4), (3, 5)], pairs);
+    assert_eq!(5, (1..=5).offset_pairs(0).filter(|(a, b)| a == b).count());
+    assert_eq!(0, (1..=5).offset_pairs(5).count());
+    assert_eq!(0, SONAR.iter().count_increases(10));
+    assert_eq!(None, SONAR.iter().copied().window_sums(11).next());
+    assert_eq!(Some(2256), SONAR.iter().copied().window_sums(10).next());
+}